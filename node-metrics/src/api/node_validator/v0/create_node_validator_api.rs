@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use super::{get_stake_table_from_sequencer, ProcessNodeIdentityUrlStreamTask};
+use super::{bootstrap_data_state, ProcessNodeIdentityUrlStreamTask};
 use crate::service::{
     client_id::ClientId,
     client_message::InternalClientMessage,
@@ -9,7 +9,7 @@ use crate::service::{
         ProcessDistributeBlockDetailHandlingTask, ProcessDistributeNodeIdentityHandlingTask,
         ProcessDistributeVotersHandlingTask,
     },
-    data_state::{DataState, ProcessLeafStreamTask, ProcessNodeIdentityStreamTask},
+    data_state::{ProcessLeafStreamTask, ProcessNodeIdentityStreamTask},
     server_message::ServerMessage,
 };
 use async_std::{sync::RwLock, task::JoinHandle};
@@ -290,13 +290,10 @@ pub async fn create_node_validator_processing(
 
     let client_stake_table = surf_disco::Client::new(config.stake_table_url_base.clone());
 
-    let stake_table = get_stake_table_from_sequencer(client_stake_table)
+    let data_state = bootstrap_data_state(&client_stake_table)
         .await
         .map_err(CreateNodeValidatorProcessingError::FailedToGetStakeTable)?;
 
-    let data_state = DataState::new(Default::default(), Default::default(), stake_table);
-
-    let data_state = Arc::new(RwLock::new(data_state));
     let client_thread_state = Arc::new(RwLock::new(client_thread_state));
     let (block_detail_sender, block_detail_receiver) = mpsc::channel(32);
     let (node_identity_sender_1, node_identity_receiver_1) = mpsc::channel(32);
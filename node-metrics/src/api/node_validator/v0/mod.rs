@@ -2,9 +2,9 @@ pub mod cdn;
 pub mod create_node_validator_api;
 
 use crate::service::client_message::{ClientMessage, InternalClientMessage};
-use crate::service::data_state::{LocationDetails, NodeIdentity};
+use crate::service::data_state::{DataState, LocationDetails, NodeIdentity};
 use crate::service::server_message::ServerMessage;
-use async_std::task::JoinHandle;
+use async_std::{sync::RwLock, task::JoinHandle};
 use espresso_types::{BackoffParams, SeqTypes};
 use futures::channel::mpsc::SendError;
 use futures::future::Either;
@@ -25,6 +25,7 @@ use std::future::Future;
 use std::io::BufRead;
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 use tide_disco::socket::Connection;
 use tide_disco::{api::ApiError, Api};
@@ -350,6 +351,41 @@ pub async fn get_stake_table_from_sequencer(
     Ok(stake_table)
 }
 
+/// [StakeTableSource] represents a source from which the current stake table
+/// can be retrieved, such as the Hotshot Query Service.
+#[async_trait::async_trait]
+pub trait StakeTableSource: Sync {
+    /// [stake_table] retrieves the current stake table from the source.
+    async fn stake_table(
+        &self,
+    ) -> Result<StakeTable<BLSPubKey, StateVerKey, CircuitField>, hotshot_query_service::Error>;
+}
+
+#[async_trait::async_trait]
+impl StakeTableSource for surf_disco::Client<hotshot_query_service::Error, Version01> {
+    async fn stake_table(
+        &self,
+    ) -> Result<StakeTable<BLSPubKey, StateVerKey, CircuitField>, hotshot_query_service::Error>
+    {
+        get_stake_table_from_sequencer(self.clone()).await
+    }
+}
+
+/// [bootstrap_data_state] creates a [DataState] that is seeded with the
+/// current stake table retrieved from the given [StakeTableSource], so that
+/// it is ready to be used before the live leaf stream starts.  A
+/// [NodeIdentity] is created for every node within the stake table.
+pub async fn bootstrap_data_state<S>(
+    source: &S,
+) -> Result<Arc<RwLock<DataState>>, hotshot_query_service::Error>
+where
+    S: StakeTableSource + ?Sized,
+{
+    let stake_table = source.stake_table().await?;
+    let data_state = DataState::new(Default::default(), Default::default(), stake_table);
+    Ok(Arc::new(RwLock::new(data_state)))
+}
+
 pub enum GetNodeIdentityFromUrlError {
     Url(url::ParseError),
     Reqwest(reqwest::Error),
@@ -974,8 +1010,80 @@ impl Drop for ProcessNodeIdentityUrlStreamTask {
 
 #[cfg(test)]
 mod tests {
+    use super::{bootstrap_data_state, StakeTableSource};
+    use ethers::types::U256;
+    use hotshot_stake_table::vec_based::StakeTable;
+    use hotshot_types::{
+        light_client::{CircuitField, StateKeyPair, StateVerKey},
+        signature_key::BLSPubKey,
+        traits::{
+            signature_key::SignatureKey,
+            stake_table::{SnapshotVersion, StakeTableScheme},
+        },
+    };
     use std::io::{BufRead, BufReader};
 
+    /// [MockStakeTableSource] serves a stake table with the given stakes, in
+    /// place of the Hotshot Query Service.
+    struct MockStakeTableSource {
+        stakes: Vec<u64>,
+    }
+
+    impl MockStakeTableSource {
+        fn public_key(index: usize) -> BLSPubKey {
+            BLSPubKey::generated_from_seed_indexed([0; 32], index as u64).0
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl StakeTableSource for MockStakeTableSource {
+        async fn stake_table(
+            &self,
+        ) -> Result<StakeTable<BLSPubKey, StateVerKey, CircuitField>, hotshot_query_service::Error>
+        {
+            let mut stake_table = StakeTable::new(self.stakes.len());
+            for (index, stake) in self.stakes.iter().enumerate() {
+                let state_key = StateKeyPair::generate_from_seed_indexed([0; 32], index as u64);
+                stake_table
+                    .register(
+                        Self::public_key(index),
+                        U256::from(*stake),
+                        state_key.ver_key(),
+                    )
+                    .expect("registering stake table entry");
+            }
+
+            stake_table.advance();
+            stake_table.advance();
+            Ok(stake_table)
+        }
+    }
+
+    #[async_std::test]
+    async fn test_bootstrap_data_state() {
+        let source = MockStakeTableSource {
+            stakes: vec![10, 20],
+        };
+        let data_state = bootstrap_data_state(&source).await.unwrap();
+        let data_state = data_state.read().await;
+
+        assert_eq!(
+            data_state
+                .node_identity()
+                .map(|node_identity| *node_identity.public_key())
+                .collect::<Vec<_>>(),
+            vec![
+                MockStakeTableSource::public_key(0),
+                MockStakeTableSource::public_key(1)
+            ]
+        );
+        assert_eq!(
+            data_state.total_stake(SnapshotVersion::Head),
+            Some(U256::from(30))
+        );
+        assert_eq!(data_state.latest_blocks().count(), 0);
+    }
+
     fn example_prometheus_output() -> &'static str {
         include_str!("example_prometheus_metrics_output.txt")
     }
@@ -0,0 +1,68 @@
+use espresso_types::SeqTypes;
+use futures::{Stream, StreamExt};
+use hotshot_query_service::Leaf;
+use std::time::Instant;
+use time::OffsetDateTime;
+
+/// [AnnotatedLeaf] is a [Leaf] that carries metadata about when it was
+/// received.  The sequence number increases monotonically for every [Leaf]
+/// in the stream, and the ingestion time records when the [Leaf] was first
+/// received from the stream, so that the delivery of leaves can be
+/// correlated with their processing.  The wall clock time at which the
+/// [Leaf] was received is recorded as well, so that it can be compared
+/// against the timestamp of its block.
+///
+/// The sequence numbers are assigned as the leaves are received, so they
+/// are always contiguous.  Leaves that are dropped before they reach the
+/// stream are detected from gaps in the block heights instead.
+#[derive(Debug, Clone)]
+pub struct AnnotatedLeaf {
+    pub leaf: Leaf<SeqTypes>,
+    pub sequence: u64,
+    pub ingested_at: Instant,
+    pub received_at: OffsetDateTime,
+}
+
+/// [annotate_leaf_stream] wraps the given [Stream] of [Leaf]s so that each
+/// [Leaf] is annotated with its sequence number within the [Stream] and the
+/// time that it was received.
+pub fn annotate_leaf_stream<S>(stream: S) -> impl Stream<Item = AnnotatedLeaf>
+where
+    S: Stream<Item = Leaf<SeqTypes>>,
+{
+    stream.enumerate().map(|(sequence, leaf)| AnnotatedLeaf {
+        leaf,
+        sequence: sequence as u64,
+        ingested_at: Instant::now(),
+        received_at: OffsetDateTime::now_utc(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::annotate_leaf_stream;
+    use espresso_types::{Leaf, NodeState, ValidatedState};
+    use futures::StreamExt;
+
+    #[async_std::test]
+    async fn test_annotate_leaf_stream() {
+        let leaf = Leaf::genesis(&ValidatedState::default(), &NodeState::mock()).await;
+        let annotated_leaves = annotate_leaf_stream(futures::stream::iter(vec![leaf; 3]))
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            annotated_leaves
+                .iter()
+                .map(|annotated_leaf| annotated_leaf.sequence)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert!(annotated_leaves
+            .windows(2)
+            .all(|pair| pair[0].ingested_at <= pair[1].ingested_at));
+        assert!(annotated_leaves
+            .windows(2)
+            .all(|pair| pair[0].received_at <= pair[1].received_at));
+    }
+}
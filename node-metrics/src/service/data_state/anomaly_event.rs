@@ -0,0 +1,36 @@
+use super::ForkEvent;
+use serde::{Deserialize, Serialize};
+
+/// [AnomalyEvent] is a structured representation of the anomalies that are
+/// otherwise only surfaced as warnings while processing leaves.  It allows
+/// alerting to subscribe to the anomalies directly, instead of having to
+/// parse the logs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnomalyEvent {
+    /// Fork indicates that two distinct blocks have been observed for the
+    /// same height.
+    Fork(ForkEvent),
+
+    /// VotersBeyondStakeTable indicates that voters were set beyond the end
+    /// of the stake table, and could not be attributed to a stake table
+    /// entry.
+    VotersBeyondStakeTable {
+        voters_beyond: usize,
+        stake_table_len: usize,
+    },
+
+    /// EmptyVoters indicates that a block was decided without any voters.
+    EmptyVoters { height: u64 },
+
+    /// PayloadUnavailable indicates that the payload of a block was not
+    /// available when it was processed.
+    PayloadUnavailable { height: u64 },
+
+    /// TimestampAhead indicates that a block was observed before its own
+    /// timestamp, which suggests clock skew.
+    TimestampAhead { height: u64 },
+
+    /// LeafHeightGap indicates that the leaves with the heights from `from`
+    /// to `to`, inclusive, were skipped before they reached the leaf stream.
+    LeafHeightGap { from: u64, to: u64 },
+}
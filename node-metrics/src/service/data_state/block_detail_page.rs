@@ -0,0 +1,27 @@
+use super::NodeIdentity;
+use espresso_types::SeqTypes;
+use ethers::types::U256;
+use hotshot_query_service::explorer::BlockDetail;
+use hotshot_types::signature_key::BLSPubKey;
+use serde::{Deserialize, Serialize};
+
+/// [BlockDetailPage] combines everything that is known about a single
+/// retained block into one view, for the block detail page.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlockDetailPage {
+    pub block: BlockDetail<SeqTypes>,
+
+    /// voters are the [NodeIdentity]s of the nodes that voted for the block,
+    /// if the voters for the block have been retained.  Nodes that have not
+    /// announced an identity are represented by their public key alone.
+    pub voters: Option<Vec<NodeIdentity>>,
+
+    /// missing_voters are the public keys of the stake table entries that did
+    /// not vote for the block, if the voters for the block have been
+    /// retained.
+    pub missing_voters: Option<Vec<BLSPubKey>>,
+
+    /// participating_stake is the total stake of the voters for the block,
+    /// if it has been retained.
+    pub participating_stake: Option<U256>,
+}
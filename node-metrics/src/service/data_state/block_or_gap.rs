@@ -0,0 +1,18 @@
+use espresso_types::SeqTypes;
+use hotshot_query_service::explorer::BlockDetail;
+use serde::Serialize;
+
+/// [BlockOrGap] is an entry of the height ordered sequence of retained
+/// blocks, in which the heights that are missing from the retained range are
+/// marked explicitly, so that they can be rendered as gaps.
+#[derive(Debug, Serialize)]
+pub enum BlockOrGap<'a> {
+    Block(&'a BlockDetail<SeqTypes>),
+
+    /// Gap marks the heights from `from` to `to`, inclusive, that are not
+    /// retained.
+    Gap {
+        from: u64,
+        to: u64,
+    },
+}
@@ -0,0 +1,22 @@
+use espresso_types::SeqTypes;
+use ethers::types::U256;
+use hotshot_query_service::availability::BlockHash;
+use serde::{Deserialize, Serialize};
+
+/// [BlockRow] represents the joined view of a single retained block, along
+/// with the voter participation that was recorded for it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockRow {
+    pub height: u64,
+    pub hash: BlockHash<SeqTypes>,
+    pub num_transactions: u64,
+    pub size: u64,
+
+    /// voter_count is the number of nodes that voted for the block, if the
+    /// voters for the block have been retained.
+    pub voter_count: Option<usize>,
+
+    /// participating_stake is the total stake of the voters for the block,
+    /// if it has been retained.
+    pub participating_stake: Option<U256>,
+}
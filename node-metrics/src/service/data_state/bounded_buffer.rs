@@ -0,0 +1,165 @@
+use std::collections::{vec_deque, VecDeque};
+
+/// [BoundedBuffer] is a first-in first-out buffer that retains, at most, a
+/// runtime configurable number of the most recently pushed entries.  Once
+/// the capacity has been reached, pushing a new entry will evict the oldest
+/// entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundedBuffer<T> {
+    entries: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> BoundedBuffer<T> {
+    /// [with_capacity] creates a new, empty, [BoundedBuffer] that will retain
+    /// at most `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// [set_capacity] changes the number of entries that will be retained.
+    /// If the buffer currently holds more entries than the new capacity,
+    /// the oldest entries are evicted and returned, oldest first.
+    pub fn set_capacity(&mut self, capacity: usize) -> Vec<T> {
+        self.capacity = capacity;
+        let excess = self.entries.len().saturating_sub(capacity);
+        self.entries.drain(..excess).collect()
+    }
+
+    /// [push_back] appends the given entry to the back of the buffer.  If the
+    /// buffer is full, the oldest entry is evicted and returned.  A buffer
+    /// with a capacity of zero will immediately return the given entry.
+    pub fn push_back(&mut self, entry: T) -> Option<T> {
+        if self.capacity == 0 {
+            return Some(entry);
+        }
+
+        let evicted = if self.entries.len() >= self.capacity {
+            self.entries.pop_front()
+        } else {
+            None
+        };
+
+        self.entries.push_back(entry);
+        evicted
+    }
+
+    /// [pop_front] removes and returns the oldest entry of the buffer.
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.entries.pop_front()
+    }
+
+    /// [clear] removes all of the entries of the buffer, retaining its
+    /// capacity.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> vec_deque::Iter<'_, T> {
+        self.entries.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> vec_deque::IterMut<'_, T> {
+        self.entries.iter_mut()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.entries.get(index)
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.entries.front()
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.entries.back()
+    }
+}
+
+impl<T> Default for BoundedBuffer<T> {
+    /// The default [BoundedBuffer] retains the last
+    /// [MAX_HISTORY](super::MAX_HISTORY) entries.
+    fn default() -> Self {
+        Self::with_capacity(super::MAX_HISTORY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BoundedBuffer;
+
+    #[test]
+    fn test_bounded_buffer_push_back_evicts_oldest() {
+        let mut buffer = BoundedBuffer::with_capacity(2);
+        assert_eq!(buffer.push_back(1), None);
+        assert_eq!(buffer.push_back(2), None);
+        assert_eq!(buffer.push_back(3), Some(1));
+
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.front(), Some(&2));
+        assert_eq!(buffer.back(), Some(&3));
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_bounded_buffer_set_capacity() {
+        let mut buffer = BoundedBuffer::with_capacity(4);
+        for entry in 0..4 {
+            buffer.push_back(entry);
+        }
+
+        assert_eq!(buffer.set_capacity(2), vec![0, 1]);
+        assert_eq!(buffer.capacity(), 2);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_bounded_buffer_pop_front() {
+        let mut buffer = BoundedBuffer::with_capacity(2);
+        buffer.push_back(1);
+        buffer.push_back(2);
+
+        assert_eq!(buffer.pop_front(), Some(1));
+        assert_eq!(buffer.pop_front(), Some(2));
+        assert_eq!(buffer.pop_front(), None);
+    }
+
+    #[test]
+    fn test_bounded_buffer_clear() {
+        let mut buffer = BoundedBuffer::with_capacity(2);
+        buffer.push_back(1);
+        buffer.push_back(2);
+        buffer.clear();
+
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), 2);
+    }
+
+    #[test]
+    fn test_bounded_buffer_zero_capacity() {
+        let mut buffer = BoundedBuffer::with_capacity(0);
+        assert_eq!(buffer.push_back(1), Some(1));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_bounded_buffer_default_capacity() {
+        let buffer: BoundedBuffer<u64> = Default::default();
+        assert_eq!(buffer.capacity(), super::super::MAX_HISTORY);
+    }
+}
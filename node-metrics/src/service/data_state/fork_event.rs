@@ -0,0 +1,13 @@
+use espresso_types::SeqTypes;
+use hotshot_query_service::availability::BlockHash;
+use serde::{Deserialize, Serialize};
+
+/// [ForkEvent] records that two distinct blocks have been observed for the
+/// same height.  hash_a is the hash of the block that was observed first,
+/// and hash_b is the hash of the conflicting block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForkEvent {
+    pub height: u64,
+    pub hash_a: BlockHash<SeqTypes>,
+    pub hash_b: BlockHash<SeqTypes>,
+}
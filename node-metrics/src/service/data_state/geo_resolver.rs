@@ -0,0 +1,164 @@
+use super::{LocationDetails, NodeIdentity};
+use async_std::sync::RwLock;
+use std::{collections::HashMap, net::IpAddr};
+use url::Host;
+
+/// [GeoResolver] resolves the [LocationDetails] of a node from its IP
+/// address.  It is used to populate the location of a [NodeIdentity] that
+/// has not announced one for itself.
+#[async_trait::async_trait]
+pub trait GeoResolver: Send + Sync {
+    /// [resolve] returns the [LocationDetails] for the given IP address, if
+    /// they can be determined.
+    async fn resolve(&self, ip: IpAddr) -> Option<LocationDetails>;
+}
+
+/// [NoOpGeoResolver] is a [GeoResolver] that never resolves a location.  It
+/// is the default for deployments that do not have a geolocation service.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoOpGeoResolver;
+
+#[async_trait::async_trait]
+impl GeoResolver for NoOpGeoResolver {
+    async fn resolve(&self, _ip: IpAddr) -> Option<LocationDetails> {
+        None
+    }
+}
+
+/// [CachingGeoResolver] wraps another [GeoResolver], and remembers the result
+/// of resolving each IP address so that the underlying resolver is only
+/// consulted once per address.  Failures to resolve are remembered as well.
+pub struct CachingGeoResolver<R> {
+    resolver: R,
+    cache: RwLock<HashMap<IpAddr, Option<LocationDetails>>>,
+}
+
+impl<R> CachingGeoResolver<R> {
+    pub fn new(resolver: R) -> Self {
+        Self {
+            resolver,
+            cache: Default::default(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<R: GeoResolver> GeoResolver for CachingGeoResolver<R> {
+    async fn resolve(&self, ip: IpAddr) -> Option<LocationDetails> {
+        if let Some(location) = self.cache.read().await.get(&ip) {
+            return location.clone();
+        }
+
+        let location = self.resolver.resolve(ip).await;
+        self.cache.write().await.insert(ip, location.clone());
+        location
+    }
+}
+
+/// [node_identity_ip_addr] returns the IP address of the given
+/// [NodeIdentity], if its public url refers to the node by IP address rather
+/// than by domain name.
+pub fn node_identity_ip_addr(node_identity: &NodeIdentity) -> Option<IpAddr> {
+    match node_identity.public_url().as_ref()?.host()? {
+        Host::Ipv4(ip) => Some(IpAddr::V4(ip)),
+        Host::Ipv6(ip) => Some(IpAddr::V6(ip)),
+        Host::Domain(_) => None,
+    }
+}
+
+/// [resolve_missing_location] populates the location of the given
+/// [NodeIdentity] using the given [GeoResolver], if the node has not
+/// announced a location, and announced an IP address.  An announced location
+/// is never replaced.
+pub async fn resolve_missing_location<R>(resolver: &R, node_identity: &mut NodeIdentity)
+where
+    R: GeoResolver + ?Sized,
+{
+    if node_identity.location.is_some() {
+        return;
+    }
+
+    let Some(ip) = node_identity_ip_addr(node_identity) else {
+        return;
+    };
+
+    node_identity.location = resolver.resolve(ip).await;
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{resolve_missing_location, CachingGeoResolver, GeoResolver, NoOpGeoResolver};
+    use crate::service::data_state::{LocationDetails, NodeIdentity};
+    use hotshot_types::{signature_key::BLSPubKey, traits::signature_key::SignatureKey};
+    use std::{
+        net::IpAddr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+    use surf_disco::Url;
+
+    /// [MockGeoResolver] resolves every IP address to the same location, and
+    /// counts the number of times it has been asked to.
+    #[derive(Default)]
+    pub struct MockGeoResolver {
+        pub resolutions: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl GeoResolver for MockGeoResolver {
+        async fn resolve(&self, _ip: IpAddr) -> Option<LocationDetails> {
+            self.resolutions.fetch_add(1, Ordering::SeqCst);
+            Some(LocationDetails::new(
+                Some((40.7128, -74.0060)),
+                Some("US".to_string()),
+            ))
+        }
+    }
+
+    fn create_test_node_identity(public_url: &str) -> NodeIdentity {
+        let public_key = BLSPubKey::generated_from_seed_indexed([0; 32], 0).0;
+        let mut node_identity = NodeIdentity::from_public_key(public_key);
+        node_identity.public_url = Some(Url::parse(public_url).unwrap());
+        node_identity
+    }
+
+    #[async_std::test]
+    async fn test_resolve_missing_location() {
+        let resolver = MockGeoResolver::default();
+
+        let mut node_identity = create_test_node_identity("http://192.0.2.1:8080/");
+        resolve_missing_location(&resolver, &mut node_identity).await;
+        assert_eq!(
+            node_identity.location(),
+            Some(&LocationDetails::new(
+                Some((40.7128, -74.0060)),
+                Some("US".to_string()),
+            ))
+        );
+
+        // A node that is only known by domain name cannot be resolved.
+        let mut node_identity = create_test_node_identity("https://example.com/");
+        resolve_missing_location(&resolver, &mut node_identity).await;
+        assert_eq!(node_identity.location(), None);
+
+        // An announced location is not replaced.
+        let announced = LocationDetails::new(None, Some("CA".to_string()));
+        let mut node_identity = create_test_node_identity("http://192.0.2.1:8080/");
+        node_identity.location = Some(announced.clone());
+        resolve_missing_location(&resolver, &mut node_identity).await;
+        assert_eq!(node_identity.location(), Some(&announced));
+
+        assert_eq!(resolver.resolutions.load(Ordering::SeqCst), 1);
+    }
+
+    #[async_std::test]
+    async fn test_caching_geo_resolver() {
+        let resolver = CachingGeoResolver::new(MockGeoResolver::default());
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+
+        assert!(resolver.resolve(ip).await.is_some());
+        assert!(resolver.resolve(ip).await.is_some());
+        assert_eq!(resolver.resolver.resolutions.load(Ordering::SeqCst), 1);
+
+        assert_eq!(NoOpGeoResolver.resolve(ip).await, None);
+    }
+}
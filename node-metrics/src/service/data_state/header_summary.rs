@@ -0,0 +1,41 @@
+use espresso_types::{FeeAccount, SeqTypes};
+use hotshot_query_service::{
+    availability::BlockHash,
+    explorer::{BlockDetail, Timestamp},
+};
+use serde::{Deserialize, Serialize};
+
+/// [HeaderSummary] is the minimal, header-like subset of a [BlockDetail]
+/// that external consumers need in order to identify a block.  It omits the
+/// reward and size details of the block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderSummary {
+    pub height: u64,
+    pub hash: BlockHash<SeqTypes>,
+    pub time: Timestamp,
+    pub proposer_id: Vec<FeeAccount>,
+}
+
+impl From<&BlockDetail<SeqTypes>> for HeaderSummary {
+    fn from(block: &BlockDetail<SeqTypes>) -> Self {
+        Self {
+            height: block.height,
+            hash: block.hash,
+            time: block.time,
+            proposer_id: block.proposer_id.clone(),
+        }
+    }
+}
+
+/// [ToHeaderSummary] allows a [HeaderSummary] to be derived directly from a
+/// [BlockDetail], without naming the [HeaderSummary] type at the call site.
+pub trait ToHeaderSummary {
+    /// [to_header_summary] returns the [HeaderSummary] of this block.
+    fn to_header_summary(&self) -> HeaderSummary;
+}
+
+impl ToHeaderSummary for BlockDetail<SeqTypes> {
+    fn to_header_summary(&self) -> HeaderSummary {
+        HeaderSummary::from(self)
+    }
+}
@@ -0,0 +1,62 @@
+use espresso_types::SeqTypes;
+use futures::{future, Stream, StreamExt};
+use hotshot_query_service::explorer::BlockDetail;
+
+/// [milestone_stream] wraps the given [Stream] of [BlockDetail]s so that only
+/// the crossing of a multiple of `interval` in height is emitted, carrying
+/// the height of the milestone that was crossed.  This is intended for
+/// lightweight consumers that only care about round number heights.
+///
+/// Should a single block cross more than one milestone, only the highest is
+/// emitted.  Blocks that are not above the highest height that has been seen
+/// so far, such as replayed blocks, never cross a milestone.  The first block
+/// is only considered to cross a milestone if its height is a milestone
+/// itself.  An interval of zero is treated as one.
+pub fn milestone_stream<S>(stream: S, interval: u64) -> impl Stream<Item = u64>
+where
+    S: Stream<Item = BlockDetail<SeqTypes>>,
+{
+    let interval = interval.max(1);
+    stream
+        .scan(None, move |highest_height: &mut Option<u64>, block| {
+            let height = block.height;
+            let milestone = match *highest_height {
+                Some(highest) if height <= highest => None,
+                Some(highest) if height / interval == highest / interval => None,
+                None if height % interval != 0 => None,
+                _ => Some(height - height % interval),
+            };
+
+            *highest_height = Some(highest_height.map_or(height, |highest| highest.max(height)));
+
+            future::ready(Some(milestone))
+        })
+        .filter_map(future::ready)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::milestone_stream;
+    use crate::service::data_state::create_block_detail_from_leaf;
+    use espresso_types::{Leaf, NodeState, ValidatedState};
+    use futures::StreamExt;
+    use hotshot_query_service::explorer::BlockDetail;
+
+    #[async_std::test]
+    async fn test_milestone_stream() {
+        let leaf = Leaf::genesis(&ValidatedState::default(), &NodeState::mock()).await;
+        let blocks = [998, 999, 1000, 1001, 999, 1000, 1999, 3500, 4000]
+            .into_iter()
+            .map(|height| BlockDetail {
+                height,
+                ..create_block_detail_from_leaf(&leaf)
+            })
+            .collect::<Vec<_>>();
+
+        let milestones = milestone_stream(futures::stream::iter(blocks), 1000)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(milestones, vec![1000, 3000, 4000]);
+    }
+}
@@ -0,0 +1,81 @@
+use super::NodeIdentity;
+use futures::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// [IdentityBatch] is a collection of [NodeIdentity] events that were
+/// received within a short window of each other, in the order that they
+/// were received.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdentityBatch {
+    pub node_identities: Vec<NodeIdentity>,
+}
+
+/// [coalesce_identity_stream] wraps the given [Stream] of [NodeIdentity]s so
+/// that the identities that are received within `window` of the first
+/// identity of a batch are emitted together as a single [IdentityBatch].
+/// This smooths out the flood of identities that is received when many
+/// nodes announce themselves at once, such as after a network restart.
+///
+/// A batch is never empty, and is emitted as soon as its window has elapsed,
+/// or the underlying [Stream] has ended.  Consumers that want every
+/// [NodeIdentity] to be delivered on its own should use the underlying
+/// [Stream] directly.
+pub fn coalesce_identity_stream<S>(stream: S, window: Duration) -> impl Stream<Item = IdentityBatch>
+where
+    S: Stream<Item = NodeIdentity> + Unpin,
+{
+    stream::unfold(Some(stream), move |stream| async move {
+        let mut stream = stream?;
+        let first = stream.next().await?;
+        let deadline = Instant::now() + window;
+        let mut node_identities = vec![first];
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match async_std::future::timeout(remaining, stream.next()).await {
+                Ok(Some(node_identity)) => node_identities.push(node_identity),
+                // The underlying stream has ended, so this is the last batch.
+                Ok(None) => return Some((IdentityBatch { node_identities }, None)),
+                Err(_) => break,
+            }
+        }
+
+        Some((IdentityBatch { node_identities }, Some(stream)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::coalesce_identity_stream;
+    use crate::service::data_state::node_identity::tests::create_test_node;
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn test_coalesce_identity_stream() {
+        let rapid = futures::stream::iter((0..3).map(create_test_node));
+        let delayed = futures::stream::once(async {
+            async_std::task::sleep(Duration::from_millis(200)).await;
+            create_test_node(3)
+        });
+
+        let batches =
+            coalesce_identity_stream(Box::pin(rapid.chain(delayed)), Duration::from_millis(50))
+                .collect::<Vec<_>>()
+                .await;
+
+        // The rapid identities are coalesced into a single batch, and the
+        // delayed identity arrives after the window has elapsed.
+        assert_eq!(
+            batches
+                .iter()
+                .map(|batch| batch.node_identities.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                (0..3).map(create_test_node).collect::<Vec<_>>(),
+                vec![create_test_node(3)],
+            ]
+        );
+    }
+}
@@ -1,13 +1,48 @@
+pub mod annotated_leaf;
+pub mod anomaly_event;
+pub mod block_detail_page;
+pub mod block_or_gap;
+pub mod block_row;
+pub mod bounded_buffer;
+pub mod fork_event;
+pub mod geo_resolver;
+pub mod header_summary;
+pub mod height_milestone;
+pub mod identity_batch;
 pub mod location_details;
+pub mod network_summary;
 pub mod node_identity;
+pub mod node_profile;
+pub mod rate_limited_warning;
+pub mod running_aggregates;
+pub mod trend;
+pub mod voter_attribution_source;
+pub mod voter_stake_snapshot;
 
+use crate::service::client_state::clone_block_detail;
+pub use annotated_leaf::{annotate_leaf_stream, AnnotatedLeaf};
+pub use anomaly_event::AnomalyEvent;
+use ark_serialize::CanonicalSerialize;
 use async_std::{sync::RwLock, task::JoinHandle};
 use bitvec::vec::BitVec;
-use circular_buffer::CircularBuffer;
-use espresso_types::{Header, Payload, SeqTypes};
-use futures::{channel::mpsc::SendError, Sink, SinkExt, Stream, StreamExt};
+pub use block_detail_page::BlockDetailPage;
+pub use block_or_gap::BlockOrGap;
+pub use block_row::BlockRow;
+pub use bounded_buffer::BoundedBuffer;
+use espresso_types::{FeeAccount, FeeAmount, Header, NsTable, Payload, SeqTypes, Transaction};
+use ethers::types::U256;
+pub use fork_event::ForkEvent;
+use futures::{
+    channel::mpsc::{SendError, Sender},
+    Sink, SinkExt, Stream, StreamExt,
+};
+pub use geo_resolver::{
+    resolve_missing_location, CachingGeoResolver, GeoResolver, NoOpGeoResolver,
+};
+pub use header_summary::{HeaderSummary, ToHeaderSummary};
+pub use height_milestone::milestone_stream;
 use hotshot_query_service::{
-    availability::{QueryableHeader, QueryablePayload},
+    availability::{BlockHash, QueryableHeader, QueryablePayload},
     explorer::{BlockDetail, ExplorerHeader, Timestamp},
     Leaf, Resolvable,
 };
@@ -21,33 +56,273 @@ use hotshot_types::{
         BlockPayload,
     },
 };
+pub use identity_batch::{coalesce_identity_stream, IdentityBatch};
 pub use location_details::LocationDetails;
+pub use network_summary::NetworkSummary;
 pub use node_identity::NodeIdentity;
-use std::{collections::HashSet, iter::zip, sync::Arc};
-use time::OffsetDateTime;
+pub use node_profile::NodeProfile;
+pub use rate_limited_warning::RateLimitedWarning;
+pub use running_aggregates::RunningAggregates;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    iter::zip,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime, UtcOffset};
+pub use trend::Trend;
+pub use voter_attribution_source::{QuorumCertificateVoters, VoterAttributionSource};
+pub use voter_stake_snapshot::VoterStakeSnapshot;
 
-/// MAX_HISTORY represents the last N records that are stored within the
-/// DataState structure for the various different sample types.
+/// MAX_HISTORY represents the default number of the last N records that are
+/// stored within the DataState structure for the various different sample
+/// types.
 const MAX_HISTORY: usize = 50;
 
+/// PARTICIPATION_TREND_TOLERANCE is the difference in the average fraction of
+/// participating nodes that is considered to be [Trend::Flat] by
+/// [DataState::participation_trend].
+const PARTICIPATION_TREND_TOLERANCE: f64 = 0.01;
+
+/// NODE_PROFILE_VOTING_PATTERN_LENGTH is the number of the most recent voters
+/// that are included in the voting pattern of a [NodeProfile].
+const NODE_PROFILE_VOTING_PATTERN_LENGTH: usize = 20;
+
+/// MIN_IQR_BLOCK_INTERVALS is the minimum number of block intervals that are
+/// needed for [DataState::block_interval_iqr] to produce a meaningful result.
+const MIN_IQR_BLOCK_INTERVALS: usize = 4;
+
+/// DEBUG_DUMP_VERSION is the version of the document that is produced by
+/// [DataState::debug_dump].  It should be incremented whenever the layout of
+/// the document changes.
+pub const DEBUG_DUMP_VERSION: u64 = 1;
+
+/// GRAFANA_TIMESERIES_TARGETS are the targets that are understood by
+/// [DataState::grafana_timeseries_json].
+pub const GRAFANA_TIMESERIES_TARGETS: [&str; 3] = ["size", "tps", "participation"];
+
 /// [DataState] represents the state of the data that is being stored within
 /// the service.
-#[cfg_attr(test, derive(Default))]
+///
+/// The [DataState] is shared behind an [async_std::sync::RwLock], which is
+/// not poisoned when a task panics while holding it.  The guard is released
+/// as the panic unwinds, so the other readers and writers are unaffected,
+/// but any modification that was in progress is left partially applied.
+/// The internal cache of the [NetworkSummary] is guarded by a
+/// [std::sync::Mutex], which can be poisoned.  In that case the poisoning is
+/// logged, and the cache is recovered, see [recover_poisoned].
+///
+/// The capacities of the block, voters, and voter stake buffers can be
+/// configured independently.  The blocks and voters are recorded together,
+/// so any API that pairs them aligns them from the most recent entry
+/// backwards.  When the capacities differ, only the most recent entries that
+/// are present in both buffers are paired, and the older entries of the
+/// larger buffer are treated as not having a counterpart.
 pub struct DataState {
-    latest_blocks: CircularBuffer<MAX_HISTORY, BlockDetail<SeqTypes>>,
-    latest_voters: CircularBuffer<MAX_HISTORY, BitVec<u16>>,
+    latest_blocks: BoundedBuffer<BlockDetail<SeqTypes>>,
+    latest_voters: BoundedBuffer<BitVec<u16>>,
+    latest_voter_stake: BoundedBuffer<VoterStakeSnapshot>,
+
+    /// stake_table retains a snapshot of the stake table for every
+    /// [SnapshotVersion], each of which can be queried independently.
     stake_table: StakeTable<BLSPubKey, StateVerKey, CircuitField>,
+
+    /// voter_attribution_version is the [SnapshotVersion] of the stake table
+    /// that is used to attribute the voters of a Quorum Certificate.  The
+    /// canonical version is [SnapshotVersion::LastEpochStart], as that is the
+    /// version of the stake table that the signatures of the Quorum
+    /// Certificate are ordered by.
+    voter_attribution_version: SnapshotVersion,
+
+    /// voter_attribution_source extracts the voters, in stake table order,
+    /// from each processed [Leaf].  It defaults to the signatures of the
+    /// Quorum Certificate, see [QuorumCertificateVoters].
+    voter_attribution_source: Arc<dyn VoterAttributionSource>,
+
+    /// transaction_filter, when configured, determines which transactions
+    /// count toward the number of transactions and the size of each
+    /// processed block.  By default, every transaction is counted.  See
+    /// [create_block_detail_from_leaf_with_filter].
+    transaction_filter: Option<Arc<dyn Fn(&Transaction) -> bool + Send + Sync>>,
+
+    /// stake_table_updated_at is the time at which the stake table was most
+    /// recently replaced, if it has been replaced since the [DataState] was
+    /// created.
+    stake_table_updated_at: Option<OffsetDateTime>,
     // Do we need any other data at the moment?
     node_identity: Vec<NodeIdentity>,
+
+    /// fee_recipient_identity maps the fee recipient accounts that appear
+    /// on blocks to the public key of the node that they belong to.  The
+    /// [BlockDetail] only carries the [FeeAccount], so this relationship
+    /// needs to be supplied externally.
+    fee_recipient_identity: HashMap<FeeAccount, BLSPubKey>,
+
+    /// duplicate_leaves is the number of leaves that have been received that
+    /// were identical to the most recently stored block, and were skipped.
+    duplicate_leaves: u64,
+
+    /// fork_events records every occurrence of a block being received for a
+    /// height that is already stored with a different hash.  These are
+    /// retained for reorg forensics.
+    fork_events: BoundedBuffer<ForkEvent>,
+
+    /// recent_leaves retains the full [Leaf]s that have been most recently
+    /// processed, for deep inspection.  [Leaf]s are large, so this is
+    /// disabled by default, and is only populated when a non-zero capacity
+    /// is configured.
+    recent_leaves: BoundedBuffer<Leaf<SeqTypes>>,
+
+    /// evicted_block_sender, when configured, is handed every block that
+    /// ages out of latest_blocks, so that it can be persisted elsewhere
+    /// before it is lost.
+    evicted_block_sender: Option<Sender<BlockDetail<SeqTypes>>>,
+    evicted_block_send_failures: RateLimitedWarning,
+
+    /// anomaly_event_sender, when configured, is handed an [AnomalyEvent]
+    /// for each of the anomalies that are warned about while processing
+    /// leaves.
+    anomaly_event_sender: Option<Sender<AnomalyEvent>>,
+    anomaly_event_send_failures: RateLimitedWarning,
+
+    /// retention_window, when configured, additionally evicts the retained
+    /// blocks whose time is further than the window behind the newest
+    /// retained block time, regardless of the capacity of latest_blocks.
+    /// The retained voters and voter stake snapshots are trimmed to the
+    /// number of retained blocks, so that they cover the same window.
+    retention_window: Option<Duration>,
+
+    /// cumulative_blocks and cumulative_transactions are lifetime counters
+    /// that are not bound by the retained window.  They are only updated for
+    /// blocks above the highest_counted_height watermark, so that replayed
+    /// blocks are not counted more than once.
+    cumulative_blocks: u64,
+    cumulative_transactions: u64,
+    highest_counted_height: Option<u64>,
+
+    /// stake_table_iteration_failures is the number of times that the
+    /// entries of the stake table could not be retrieved for voter
+    /// attribution.  This is distinct from the stake table being empty.
+    stake_table_iteration_failures: u64,
+
+    /// last_leaf_height, leaf_height_gaps, and latest_ingestion_delay track
+    /// the delivery of the processed leaves.  A gap in the block heights
+    /// indicates that leaves were dropped before they reached the leaf
+    /// stream, and the ingestion delay indicates how long a leaf was held
+    /// after being received from the stream before it was processed, such as
+    /// while processing is paused.
+    last_leaf_height: Option<u64>,
+    leaf_height_gaps: u64,
+    latest_ingestion_delay: Option<Duration>,
+
+    /// finalization_lags records, for the most recently processed leaves, how
+    /// long after the block's timestamp the leaf was observed.  Lags that
+    /// would be negative, due to clock skew, are recorded as zero.
+    finalization_lags: BoundedBuffer<Duration>,
+    negative_finalization_lags: RateLimitedWarning,
+
+    /// missing_payload_heights records the heights of the retained blocks
+    /// whose payload was unavailable when they were processed.  During
+    /// catch-up, headers can arrive before their payloads, in which case
+    /// the block is recorded as though it were empty.
+    missing_payload_heights: BTreeSet<u64>,
+
+    /// payload_fallback_count is the number of leaves whose payload was
+    /// unavailable, and for which the empty [Payload] was used in its place.
+    /// Unlike missing_payload_heights, this is a lifetime counter that is not
+    /// bound by the retained window.
+    payload_fallback_count: u64,
+
+    /// voters_beyond_stake_table is the number of voter bits that have been
+    /// set in Quorum Certificates for positions beyond the end of the stake
+    /// table.  These cannot be attributed to any stake table entry, and
+    /// indicate an inconsistency between the Quorum Certificate and the
+    /// stake table.
+    voters_beyond_stake_table: u64,
+
+    /// running_aggregates are maintained for every processed leaf,
+    /// independently of the retained buffers.
+    running_aggregates: RunningAggregates,
+
+    /// voter_tracking_enabled determines whether the voters of each leaf are
+    /// attributed and recorded.  When it is disabled, no voters or voter
+    /// stake snapshots are recorded or sent, so every API that depends on
+    /// them will return empty results, or [None].
+    voter_tracking_enabled: bool,
+
+    /// genesis_handled records whether the genesis block has been processed.
+    /// The genesis block legitimately has no voters, and may have no
+    /// payload, so the warnings for those anomalies are suppressed for it.
+    genesis_handled: bool,
+
+    /// blocklisted_heights and blocklisted_hashes identify leaves that are
+    /// known to be bad, and should be skipped entirely when they are
+    /// received.  This allows operators to route around a poison block
+    /// without restarting the service.
+    blocklisted_heights: HashSet<u64>,
+    blocklisted_hashes: HashSet<BlockHash<SeqTypes>>,
+
+    /// network_summary_cache retains the most recently computed
+    /// [NetworkSummary], so that repeated requests at the same latest block
+    /// height do not recompute it.  It is cleared whenever the information
+    /// that the summary is derived from changes.
+    network_summary_cache: Mutex<Option<NetworkSummary>>,
+
+    /// tombstoned_nodes identifies the nodes that have been removed from the
+    /// network.  Their [NodeIdentity]s are retained, so that the retained
+    /// voters remain aligned, but they are excluded from the active set.
+    tombstoned_nodes: HashSet<BLSPubKey>,
+
+    /// archived_blocks is an optional long range buffer of blocks that is
+    /// intended for trend visualization.  On fast chains it is unnecessary
+    /// to keep every block, so only blocks whose height is a multiple of
+    /// archive_sampling_stride are archived.  As the archived data may be
+    /// sampled, any statistics that need to be exact should be derived from
+    /// latest_blocks instead.  This is disabled by default.
+    archived_blocks: BoundedBuffer<BlockDetail<SeqTypes>>,
+    archive_sampling_stride: u64,
+
+    /// archived_block_index maps the height of each archived block to the
+    /// sequence number it was archived with.  The position of the block
+    /// within archived_blocks can be recovered from the sequence number, as
+    /// sequence numbers are assigned in push order.  This allows for height
+    /// lookups without a linear scan of the archive.
+    archived_block_index: BTreeMap<u64, u64>,
+    archived_blocks_pushed: u64,
+
+    /// voters_first_seen maps the public key of each node to the sequence
+    /// number of the first voters that it was attributed a vote within.
+    /// Sequence numbers are assigned to the voters in push order, so the
+    /// position of a node's first voters within latest_voters can be
+    /// recovered from it, even after the voters have been reindexed.
+    voters_first_seen: HashMap<BLSPubKey, u64>,
+    voters_pushed: u64,
+}
+
+#[cfg(test)]
+impl Default for DataState {
+    fn default() -> Self {
+        Self::new(Default::default(), Default::default(), Default::default())
+    }
 }
 
 impl DataState {
+    /// [new] creates a new [DataState] with the given buffers and stake
+    /// table.  The latest blocks and voters are required for all of the
+    /// statistics, so buffers with a capacity of zero are given a capacity of
+    /// one instead.  See [retained_capacity].
     pub fn new(
-        latest_blocks: CircularBuffer<MAX_HISTORY, BlockDetail<SeqTypes>>,
-        latest_voters: CircularBuffer<MAX_HISTORY, BitVec<u16>>,
+        mut latest_blocks: BoundedBuffer<BlockDetail<SeqTypes>>,
+        mut latest_voters: BoundedBuffer<BitVec<u16>>,
         stake_table: StakeTable<BLSPubKey, StateVerKey, CircuitField>,
     ) -> Self {
-        let node_identity = {
+        latest_blocks.set_capacity(retained_capacity("latest blocks", latest_blocks.capacity()));
+        latest_voters.set_capacity(retained_capacity("latest voters", latest_voters.capacity()));
+
+        let node_identity: Vec<NodeIdentity> = {
             let stake_table_iter_result = stake_table.try_iter(SnapshotVersion::Head);
             match stake_table_iter_result {
                 Ok(into_iter) => into_iter
@@ -57,11 +332,60 @@ impl DataState {
             }
         };
 
+        let mut voters_first_seen = HashMap::new();
+        for (sequence, voters) in latest_voters.iter().enumerate() {
+            for node_identity in node_identity.iter().take(voters.len()) {
+                voters_first_seen
+                    .entry(*node_identity.public_key())
+                    .or_insert(sequence as u64);
+            }
+        }
+        let voters_pushed = latest_voters.len() as u64;
+
         Self {
             latest_blocks,
             latest_voters,
+            latest_voter_stake: Default::default(),
             stake_table,
             node_identity,
+            fee_recipient_identity: HashMap::new(),
+            duplicate_leaves: 0,
+            fork_events: Default::default(),
+            recent_leaves: BoundedBuffer::with_capacity(0),
+            evicted_block_sender: None,
+            evicted_block_send_failures: Default::default(),
+            anomaly_event_sender: None,
+            anomaly_event_send_failures: Default::default(),
+            retention_window: None,
+            cumulative_blocks: 0,
+            cumulative_transactions: 0,
+            highest_counted_height: None,
+            stake_table_iteration_failures: 0,
+            last_leaf_height: None,
+            leaf_height_gaps: 0,
+            latest_ingestion_delay: None,
+            finalization_lags: BoundedBuffer::with_capacity(MAX_HISTORY),
+            negative_finalization_lags: RateLimitedWarning::default(),
+            missing_payload_heights: BTreeSet::new(),
+            payload_fallback_count: 0,
+            voters_beyond_stake_table: 0,
+            running_aggregates: Default::default(),
+            voter_tracking_enabled: true,
+            genesis_handled: false,
+            blocklisted_heights: HashSet::new(),
+            blocklisted_hashes: HashSet::new(),
+            network_summary_cache: Mutex::new(None),
+            tombstoned_nodes: HashSet::new(),
+            voter_attribution_version: SnapshotVersion::LastEpochStart,
+            voter_attribution_source: Arc::new(QuorumCertificateVoters),
+            transaction_filter: None,
+            stake_table_updated_at: None,
+            archived_blocks: BoundedBuffer::with_capacity(0),
+            archive_sampling_stride: 1,
+            archived_block_index: BTreeMap::new(),
+            archived_blocks_pushed: 0,
+            voters_first_seen,
+            voters_pushed,
         }
     }
 
@@ -69,724 +393,6825 @@ impl DataState {
         self.latest_blocks.iter()
     }
 
-    pub fn latest_voters(&self) -> impl Iterator<Item = &BitVec<u16>> {
-        self.latest_voters.iter()
+    /// [blocks_sorted_by_height] returns the retained blocks sorted by
+    /// ascending height.  The blocks are retained in the order that they
+    /// were received, which may not be the order of their heights.  Unlike
+    /// [latest_blocks], which iterates the blocks in insertion order, this
+    /// sorts them, and is O(n log n) in the number of retained blocks.
+    /// Blocks with equal heights keep their insertion order.
+    pub fn blocks_sorted_by_height(&self) -> Vec<&BlockDetail<SeqTypes>> {
+        let mut blocks = self.latest_blocks.iter().collect::<Vec<_>>();
+        blocks.sort_by_key(|block| block.height);
+        blocks
     }
 
-    pub fn stake_table(&self) -> &StakeTable<BLSPubKey, StateVerKey, CircuitField> {
-        &self.stake_table
+    /// [latest_block] returns the most recently retained block.
+    pub fn latest_block(&self) -> Option<&BlockDetail<SeqTypes>> {
+        self.latest_blocks.back()
     }
 
-    pub fn node_identity(&self) -> impl Iterator<Item = &NodeIdentity> {
-        self.node_identity.iter()
+    /// [latest_block_height] returns the height of the most recently retained
+    /// block.
+    pub fn latest_block_height(&self) -> Option<u64> {
+        self.latest_block().map(|block| block.height)
     }
 
-    pub fn replace_stake_table(
-        &mut self,
-        stake_table: StakeTable<BLSPubKey, StateVerKey, CircuitField>,
-    ) {
-        self.stake_table = stake_table;
+    /// [latest_fee_recipient] returns the fee recipient of the most recent
+    /// retained block.  Should the block have more than one fee recipient,
+    /// the first is returned.
+    ///
+    /// Returns [None] if there are no retained blocks.
+    pub fn latest_fee_recipient(&self) -> Option<FeeAccount> {
+        self.latest_block()?.fee_recipient.first().copied()
+    }
 
-        // We want to make sure that we're accounting for this node identity
-        // information that we have.  In the case of any new public keys
-        // being added, we want to ensure we have an entry for them in our
-        // node identity list.
+    /// [oldest_block] returns the oldest retained block, which represents the
+    /// lower bound of the retained window.
+    pub fn oldest_block(&self) -> Option<&BlockDetail<SeqTypes>> {
+        self.latest_blocks.front()
+    }
 
-        let current_identity_set = self
-            .node_identity
+    /// [oldest_block_height] returns the height of the oldest retained block.
+    pub fn oldest_block_height(&self) -> Option<u64> {
+        self.oldest_block().map(|block| block.height)
+    }
+
+    /// [missing_heights] returns, in ascending order, the heights from the
+    /// given authoritative list that fall within the retained window of
+    /// blocks, but that have not been retained.  These are the heights of
+    /// leaves that were likely dropped, and should be re-fetched.
+    pub fn missing_heights(&self, authoritative: &[u64]) -> Vec<u64> {
+        let retained_heights = self
+            .latest_blocks
             .iter()
-            .map(|node_identity| *node_identity.public_key())
+            .map(|block| block.height)
             .collect::<HashSet<_>>();
 
-        let stake_table_iter_result = self.stake_table.try_iter(SnapshotVersion::Head);
-        let stake_table_iter = match stake_table_iter_result {
-            Ok(into_iter) => into_iter,
-            Err(_) => return,
+        let (Some(&lowest), Some(&highest)) =
+            (retained_heights.iter().min(), retained_heights.iter().max())
+        else {
+            return Vec::new();
         };
 
-        let missing_node_identity_entries =
-            stake_table_iter.filter(|(key, _, _)| !current_identity_set.contains(key));
-
-        self.node_identity.extend(
-            missing_node_identity_entries.map(|(key, _, _)| NodeIdentity::from_public_key(key)),
-        );
+        authoritative
+            .iter()
+            .copied()
+            .filter(|height| (lowest..=highest).contains(height))
+            .filter(|height| !retained_heights.contains(height))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
     }
 
-    pub fn add_latest_block(&mut self, block: BlockDetail<SeqTypes>) {
-        self.latest_blocks.push_back(block);
+    /// [blocks_with_gaps] returns the retained blocks in ascending order of
+    /// height, with a [BlockOrGap::Gap] inserted wherever heights are missing
+    /// between two consecutive retained blocks.  Only gaps within the
+    /// retained range are marked.
+    pub fn blocks_with_gaps(&self) -> Vec<BlockOrGap<'_>> {
+        let mut blocks = self.latest_blocks.iter().collect::<Vec<_>>();
+        blocks.sort_by_key(|block| block.height);
+
+        let mut blocks_with_gaps = Vec::with_capacity(blocks.len());
+        let mut previous_height: Option<u64> = None;
+        for block in blocks {
+            // No height follows u64::MAX, so there can be no gap after it.
+            if let Some(next_height) = previous_height.and_then(|height| height.checked_add(1)) {
+                if block.height > next_height {
+                    blocks_with_gaps.push(BlockOrGap::Gap {
+                        from: next_height,
+                        to: block.height - 1,
+                    });
+                }
+            }
+
+            previous_height = Some(block.height);
+            blocks_with_gaps.push(BlockOrGap::Block(block));
+        }
+
+        blocks_with_gaps
     }
 
-    pub fn add_latest_voters(&mut self, voters: BitVec<u16>) {
-        self.latest_voters.push_back(voters);
+    pub fn latest_voters(&self) -> impl Iterator<Item = &BitVec<u16>> {
+        self.latest_voters.iter()
     }
 
-    pub fn add_node_identity(&mut self, identity: NodeIdentity) {
-        // We need to check to see if this identity is already in the list,
-        // if it is, we will want to replace it.
+    pub fn latest_voter_stake(&self) -> impl Iterator<Item = &VoterStakeSnapshot> {
+        self.latest_voter_stake.iter()
+    }
 
-        let pub_key = identity.public_key();
+    /// [normalized_voters] returns the retained voters with each [BitVec]
+    /// resized to the number of known [NodeIdentity]s.  Voters that were
+    /// recorded before more identities were known are shorter, so they are
+    /// padded with `false`, as those nodes could not have been attributed a
+    /// vote at the time.
+    pub fn normalized_voters(&self) -> Vec<BitVec<u16>> {
+        let len = self.node_identity.len();
+        self.latest_voters
+            .iter()
+            .map(|voters| {
+                let mut voters = voters.clone();
+                voters.resize(len, false);
+                voters
+            })
+            .collect()
+    }
 
-        let mut matching_public_keys = self
+    /// [voting_pattern] returns whether the node with the given public key
+    /// voted for each of the last `n` retained blocks, from oldest to newest.
+    /// Fewer than `n` entries are returned if fewer voters are retained.
+    ///
+    /// Returns [None] if the public key does not belong to a known
+    /// [NodeIdentity].
+    pub fn voting_pattern(&self, key: &BLSPubKey, n: usize) -> Option<Vec<bool>> {
+        let index = self
             .node_identity
             .iter()
-            // We want the index of the entry for easier editing
+            .position(|node_identity| node_identity.public_key() == key)?;
+
+        let normalized_voters = self.normalized_voters();
+        let skip = normalized_voters.len().saturating_sub(n);
+        Some(
+            normalized_voters
+                .iter()
+                .skip(skip)
+                .map(|voters| voters[index])
+                .collect(),
+        )
+    }
+
+    /// [voters_by_node] transposes the retained voters into a column per known
+    /// [NodeIdentity], recording whether the node voted for each of the
+    /// retained voters, from oldest to newest.
+    pub fn voters_by_node(&self) -> HashMap<BLSPubKey, Vec<bool>> {
+        let normalized_voters = self.normalized_voters();
+        self.node_identity
+            .iter()
             .enumerate()
-            .filter(|(_, node_identity)| node_identity.public_key() == pub_key);
+            .map(|(index, node_identity)| {
+                (
+                    *node_identity.public_key(),
+                    normalized_voters
+                        .iter()
+                        .map(|voters| voters[index])
+                        .collect(),
+                )
+            })
+            .collect()
+    }
 
-        // We only expect this have a single entry.
-        let existing_node_identity_option = matching_public_keys.next();
+    /// [participation_rate] returns the fraction of the retained voters in
+    /// which the node with the given public key voted.
+    ///
+    /// Returns [None] if the public key does not belong to a known
+    /// [NodeIdentity], or if no voters have been retained.
+    pub fn participation_rate(&self, key: &BLSPubKey) -> Option<f64> {
+        participation_rate(&self.voting_pattern(key, usize::MAX)?)
+    }
 
-        debug_assert_eq!(matching_public_keys.next(), None);
+    /// [participation_rate_excluding_warm_up] returns the fraction of the
+    /// retained voters, since the node with the given public key was first
+    /// seen, in which it voted.  Unlike [participation_rate], a node that was
+    /// added within the retained window is not penalized for the blocks that
+    /// predate it.  See [first_seen_index].
+    ///
+    /// Returns [None] if the public key does not belong to a known
+    /// [NodeIdentity], or if it has not been seen within any retained voters.
+    pub fn participation_rate_excluding_warm_up(&self, key: &BLSPubKey) -> Option<f64> {
+        let first_seen_index = self.first_seen_index(key)?;
+        let voting_pattern = self.voting_pattern(key, usize::MAX)?;
+        participation_rate(voting_pattern.get(first_seen_index..)?)
+    }
 
-        if let Some((index, _)) = existing_node_identity_option {
-            self.node_identity[index] = identity;
-            return;
+    /// [group_participation_rate] returns the combined fraction of the
+    /// retained voters in which the nodes with the given public keys voted.
+    /// Public keys that do not belong to a known [NodeIdentity] are ignored.
+    ///
+    /// Returns [None] if none of the public keys are known, or if no voters
+    /// have been retained.
+    pub fn group_participation_rate(&self, keys: &[BLSPubKey]) -> Option<f64> {
+        let voting_patterns = keys
+            .iter()
+            .filter_map(|key| self.voting_pattern(key, usize::MAX))
+            .collect::<Vec<_>>();
+
+        let unknown_keys = keys.len() - voting_patterns.len();
+        if unknown_keys > 0 {
+            tracing::debug!(
+                "group participation rate: ignoring {} unknown public key(s)",
+                unknown_keys
+            );
         }
 
-        // This entry doesn't appear in our table, so let's add it.
-        self.node_identity.push(identity);
+        participation_rate(&voting_patterns.concat())
     }
-}
 
-/// [create_block_detail_from_leaf] is a helper function that will build a
-/// [BlockDetail] from the reference to [Leaf].
-pub fn create_block_detail_from_leaf(leaf: &Leaf<SeqTypes>) -> BlockDetail<SeqTypes> {
-    let block_header = leaf.block_header();
-    let block_payload = &leaf.block_payload().unwrap_or(Payload::empty().0);
+    /// [stake_weighted_uptime] returns the uptime of the node with the given
+    /// public key for the purposes of stake weighting.  The uptime of a
+    /// single node is its [participation_rate](Self::participation_rate),
+    /// as weighting a node by its own stake has no effect.  The stake only
+    /// matters when aggregating across nodes, see
+    /// [network_stake_weighted_uptime](Self::network_stake_weighted_uptime).
+    ///
+    /// Returns [None] if the node is not in the stake table for the given
+    /// [SnapshotVersion], or if it has no participation rate.
+    pub fn stake_weighted_uptime(&self, key: &BLSPubKey, version: SnapshotVersion) -> Option<f64> {
+        self.stake_of(key, version)?;
+        self.participation_rate(key)
+    }
 
-    let transaction_iter = block_payload.iter(block_header.metadata());
+    /// [network_stake_weighted_uptime] aggregates the participation rates of
+    /// the nodes in the stake table for the voter attribution version,
+    /// weighting each by its stake.  Unlike an unweighted average, a node
+    /// with a larger stake has a proportionally larger effect on the result.
+    ///
+    /// Returns [None] if no node in the stake table has a participation rate,
+    /// or if their total stake is zero.
+    pub fn network_stake_weighted_uptime(&self) -> Option<f64> {
+        let entries = self
+            .stake_table
+            .try_iter(self.voter_attribution_version)
+            .ok()?;
 
-    // Calculate the number of transactions and the total payload size of the
-    // transactions contained within the Payload.
-    let (num_transactions, total_payload_size) = transaction_iter.fold(
-        (0u64, 0u64),
-        |(num_transactions, total_payload_size), tx_index| {
-            (
-                num_transactions + 1,
-                total_payload_size
-                    + block_payload
-                        .transaction(&tx_index)
-                        .map_or(0u64, |tx| tx.payload().len() as u64),
-            )
-        },
-    );
+        let (weighted_uptime, total_stake) = entries
+            .filter_map(|(key, stake, _)| {
+                let participation_rate = self.participation_rate(&key)?;
+                let stake = u256_to_f64(stake);
+                Some((participation_rate * stake, stake))
+            })
+            .fold(
+                (0.0, 0.0),
+                |(weighted_uptime, total_stake), (uptime, stake)| {
+                    (weighted_uptime + uptime, total_stake + stake)
+                },
+            );
 
-    BlockDetail::<SeqTypes> {
-        hash: block_header.commitment(),
-        height: block_header.height(),
-        time: Timestamp(
-            OffsetDateTime::from_unix_timestamp(block_header.timestamp() as i64)
-                .unwrap_or(OffsetDateTime::UNIX_EPOCH),
-        ),
-        proposer_id: block_header.proposer_id(),
-        num_transactions,
-        block_reward: vec![block_header.fee_info_balance().into()],
-        fee_recipient: block_header.fee_info_account(),
-        size: total_payload_size,
-    }
-}
+        if total_stake <= 0.0 {
+            return None;
+        }
 
-/// [ProcessLeafError] represents the error that can occur when processing
-/// a [Leaf].
-#[derive(Debug)]
-pub enum ProcessLeafError {
-    BlockSendError(SendError),
-    VotersSendError(SendError),
-}
+        Some(weighted_uptime / total_stake)
+    }
 
-impl std::fmt::Display for ProcessLeafError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ProcessLeafError::BlockSendError(err) => {
-                write!(f, "error sending block detail to sender: {}", err)
-            }
-            ProcessLeafError::VotersSendError(err) => {
-                write!(f, "error sending voters to sender: {}", err)
-            }
+    /// [silent_stake_fraction] returns the fraction of the total stake, in the
+    /// stake table for the voter attribution version, that belongs to
+    /// validators that did not vote for any of the retained blocks.  Unlike
+    /// counting silent nodes, this reflects how much of the network's weight
+    /// is absent.
+    ///
+    /// Returns [None] if no voters have been retained, if the stake table is
+    /// unavailable, or if its total stake is zero.
+    pub fn silent_stake_fraction(&self) -> Option<f64> {
+        if self.latest_voters.is_empty() {
+            return None;
         }
+
+        let entries = self
+            .stake_table
+            .try_iter(self.voter_attribution_version)
+            .ok()?;
+
+        let voters_by_node = self.voters_by_node();
+        let (silent_stake, total_stake) = entries.fold(
+            (U256::zero(), U256::zero()),
+            |(silent_stake, total_stake), (key, stake, _)| {
+                let voted = voters_by_node
+                    .get(&key)
+                    .is_some_and(|voting_pattern| voting_pattern.contains(&true));
+                if voted {
+                    (silent_stake, total_stake + stake)
+                } else {
+                    (silent_stake + stake, total_stake + stake)
+                }
+            },
+        );
+
+        stake_fraction(silent_stake, total_stake)
     }
-}
 
-impl std::error::Error for ProcessLeafError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        match self {
-            ProcessLeafError::BlockSendError(err) => Some(err),
-            ProcessLeafError::VotersSendError(err) => Some(err),
+    /// [voter_stake_entropy] computes the Shannon entropy, in bits, of the
+    /// distribution of stake amongst the voters of the most recent retained
+    /// block, using the stake table for the given [SnapshotVersion].  A low
+    /// entropy indicates that a few large validators dominate the Quorum
+    /// Certificate, while `n` voters with an equal stake have an entropy of
+    /// `log2(n)`.  Voters that are not in the stake table for the version
+    /// are not considered.
+    ///
+    /// Returns [None] if no voters have been retained, or if the voters have
+    /// no stake.
+    pub fn voter_stake_entropy(&self, version: SnapshotVersion) -> Option<f64> {
+        let voters = self.latest_voters.back()?;
+        let voter_public_keys = self.voter_public_keys(voters);
+        let stakes = self
+            .stake_table
+            .try_iter(version)
+            .ok()?
+            .filter(|(key, _, _)| voter_public_keys.contains(key))
+            .map(|(_, stake, _)| u256_to_f64(stake))
+            .collect::<Vec<_>>();
+
+        let total_stake = stakes.iter().sum::<f64>();
+        if total_stake <= 0.0 {
+            return None;
         }
+
+        Some(
+            -stakes
+                .iter()
+                .map(|stake| stake / total_stake)
+                .filter(|probability| *probability > 0.0)
+                .map(|probability| probability * probability.log2())
+                .sum::<f64>(),
+        )
     }
-}
 
-/// [process_incoming_leaf] is a helper function that will process an incoming
-/// [Leaf] and update the [DataState] with the new information.
-/// Additionally, the block that is contained within the [Leaf] will be
-/// computed into a [BlockDetail] and sent to the [Sink] so that it can be
-/// processed for real-time considerations.
-async fn process_incoming_leaf<BDSink, BVSink>(
-    leaf: Leaf<SeqTypes>,
-    data_state: Arc<RwLock<DataState>>,
-    mut block_sender: BDSink,
-    mut voters_sender: BVSink,
-) -> Result<(), ProcessLeafError>
-where
-    Header: BlockHeader<SeqTypes> + QueryableHeader<SeqTypes> + ExplorerHeader<SeqTypes>,
-    Payload: BlockPayload<SeqTypes>,
-    BDSink: Sink<BlockDetail<SeqTypes>, Error = SendError> + Unpin,
-    BVSink: Sink<BitVec<u16>, Error = SendError> + Unpin,
-{
-    let block_detail = create_block_detail_from_leaf(&leaf);
-    let block_detail_copy = create_block_detail_from_leaf(&leaf);
+    /// [node_profile] assembles the [NodeProfile] of the node with the given
+    /// public key.
+    ///
+    /// Returns [None] if the public key does not belong to a known
+    /// [NodeIdentity].
+    pub fn node_profile(&self, key: &BLSPubKey) -> Option<NodeProfile> {
+        let identity = self
+            .node_identity
+            .iter()
+            .find(|node_identity| node_identity.public_key() == key)?
+            .clone();
+        let voting_pattern = self.voting_pattern(key, usize::MAX)?;
+        let participation_rate = participation_rate(&voting_pattern);
 
-    let certificate = leaf.justify_qc();
-    let signatures = &certificate.signatures;
+        let absence_streak = voting_pattern
+            .iter()
+            .rev()
+            .take_while(|voted| !**voted)
+            .count();
 
-    // Let's take a look at the quorum certificate signatures.
-    // It looks like all of these blocks are being decided by the
-    // same Quorum Certificate.
+        let skip = voting_pattern
+            .len()
+            .saturating_sub(NODE_PROFILE_VOTING_PATTERN_LENGTH);
+        let recent_voting_pattern = voting_pattern[skip..].to_vec();
 
-    // Where's the stake table?
-    let signatures = signatures.as_ref();
+        Some(NodeProfile {
+            identity,
+            stake: self.stake_of(key, self.voter_attribution_version),
+            participation_rate,
+            absence_streak,
+            recent_voting_pattern,
+        })
+    }
 
-    // Let's determine the the participants of the voter participants
-    // in the Quorum Certificate.
+    /// [participation_trend] compares the average fraction of known nodes
+    /// that voted in the most recent `window` blocks to that of the
+    /// preceding `window` blocks.  Differences within
+    /// [PARTICIPATION_TREND_TOLERANCE] are considered to be [Trend::Flat].
+    ///
+    /// Returns [None] if fewer than `2 * window` voters are retained, or if
+    /// there are no known nodes.
+    pub fn participation_trend(&self, window: usize) -> Option<Trend> {
+        let node_count = self.node_identity.len();
+        if window == 0
+            || node_count == 0
+            || window
+                .checked_mul(2)
+                .map_or(true, |needed| self.latest_voters.len() < needed)
+        {
+            return None;
+        }
 
-    // We shouldn't ever have a BitVec that is empty, with the possible
-    // exception of the genesis block.
-    let stake_table_voters_bit_vec = signatures.map_or(Default::default(), |sig| sig.1.clone());
+        let normalized_voters = self.normalized_voters();
+        let average_participation = |voters: &[BitVec<u16>]| {
+            voters
+                .iter()
+                .map(|voters| voters.count_ones() as f64 / node_count as f64)
+                .sum::<f64>()
+                / voters.len() as f64
+        };
 
-    // This BitVec should be in the same order as the Stake Table.
-    // The StakeTable will be able to change its order between epochs,
-    // which means that its order can change between blocks.
-    // However, the BitVec is a really nice size in order for storing
-    // information.  We should be able to remap the BitVec order from
-    // the StakeTable order to our installed order representation.  This
-    // should allow us to still store as a BitVec while containing our
-    // out order of the voters.
-    // We will need to recompute these BitVecs if the node information that
-    // is stored shrinks instead of growing.
+        let recent_start = normalized_voters.len() - window;
+        let previous =
+            average_participation(&normalized_voters[recent_start - window..recent_start]);
+        let recent = average_participation(&normalized_voters[recent_start..]);
 
-    let mut data_state_write_lock_guard = data_state.write().await;
+        let difference = recent - previous;
+        if difference > PARTICIPATION_TREND_TOLERANCE {
+            Some(Trend::Up)
+        } else if difference < -PARTICIPATION_TREND_TOLERANCE {
+            Some(Trend::Down)
+        } else {
+            Some(Trend::Flat)
+        }
+    }
 
-    let stake_table = &data_state_write_lock_guard.stake_table;
-    let stable_table_entries_vec = stake_table
-        .try_iter(SnapshotVersion::LastEpochStart)
-        .map_or(vec![], |into_iter| into_iter.collect::<Vec<_>>());
-
-    // We have a BitVec of voters who signed the QC.
-    // We can use this to determine the weight of the QC
-    let stake_table_entry_voter_participation_and_entries_pairs =
-        zip(stake_table_voters_bit_vec, stable_table_entries_vec);
-    let stake_table_keys_that_voted = stake_table_entry_voter_participation_and_entries_pairs
-        .filter(|(bit_ref, _)| *bit_ref)
-        .map(|(_, entry)| {
-            // Alright this is our entry that we care about.
-            // In this case, we just want to determine who voted for this
-            // Leaf.
-
-            let (key, _, _): (BLSPubKey, _, _) = entry;
-            key
-        });
+    /// [missing_voters] returns the public keys of the stake table entries
+    /// that did not vote for the retained block with the given height.  The
+    /// stake table for the voter attribution version, excluding tombstoned
+    /// nodes, is used as the set of expected voters.
+    ///
+    /// Returns [None] if the voters for the height are not retained.
+    pub fn missing_voters(&self, height: u64) -> Option<Vec<BLSPubKey>> {
+        let voters = self.voters_for_height(height)?;
+        let stake_table_keys = self
+            .stake_table
+            .try_iter(self.voter_attribution_version)
+            .map_or(vec![], |into_iter| {
+                into_iter.map(|(key, _, _)| key).collect::<Vec<_>>()
+            });
+
+        // Before any identities are known, the voters are recorded in stake
+        // table order.  See [compute_voters_bitvec].
+        if self.node_identity.is_empty() {
+            return Some(
+                stake_table_keys
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(index, _)| !voters.get(*index).is_some_and(|voted| *voted))
+                    .map(|(_, key)| key)
+                    .filter(|key| !self.is_tombstoned(key))
+                    .collect(),
+            );
+        }
 
-    let voters_set: HashSet<BLSPubKey> = stake_table_keys_that_voted.collect();
+        let index_by_public_key = self
+            .node_identity
+            .iter()
+            .enumerate()
+            .map(|(index, node_identity)| (*node_identity.public_key(), index))
+            .collect::<HashMap<_, _>>();
 
-    let voters_bitvec = data_state_write_lock_guard.node_identity.iter().fold(
-        BitVec::with_capacity(data_state_write_lock_guard.node_identity.len()),
-        |mut acc, node_identity| {
-            acc.push(voters_set.contains(node_identity.public_key()));
-            acc
-        },
-    );
+        Some(
+            stake_table_keys
+                .into_iter()
+                .filter(|key| {
+                    !index_by_public_key
+                        .get(key)
+                        .and_then(|index| voters.get(*index))
+                        .is_some_and(|voted| *voted)
+                })
+                .filter(|key| !self.is_tombstoned(key))
+                .collect(),
+        )
+    }
 
-    data_state_write_lock_guard
-        .latest_blocks
-        .push_back(block_detail);
-    data_state_write_lock_guard
-        .latest_voters
-        .push_back(voters_bitvec.clone());
+    /// [block_detail_page] assembles the [BlockDetailPage] of the retained
+    /// block with the given height.  The voters are resolved in the same way
+    /// as [missing_voters], so that the two are complementary.
+    ///
+    /// Returns [None] if the block with the given height is not retained.
+    pub fn block_detail_page(&self, height: u64) -> Option<BlockDetailPage> {
+        let block = self
+            .latest_blocks
+            .iter()
+            .filter(|block| block.height == height)
+            .last()?;
 
-    drop(data_state_write_lock_guard);
+        let voters = self.voters_for_height(height).map(|voters| {
+            // Before any identities are known, the voters are recorded in
+            // stake table order.  See [compute_voters_bitvec].
+            if self.node_identity.is_empty() {
+                return self
+                    .stake_table
+                    .try_iter(self.voter_attribution_version)
+                    .map_or(vec![], |into_iter| {
+                        zip(voters.iter().by_vals(), into_iter)
+                            .filter(|(voted, _)| *voted)
+                            .map(|(_, (key, _, _))| NodeIdentity::from_public_key(key))
+                            .collect()
+                    });
+            }
 
-    if let Err(err) = block_sender.send(block_detail_copy).await {
-        // We have an error that prevents us from continuing
-        return Err(ProcessLeafError::BlockSendError(err));
+            zip(voters.iter().by_vals(), self.node_identity.iter())
+                .filter(|(voted, _)| *voted)
+                .map(|(_, node_identity)| node_identity.clone())
+                .collect()
+        });
+
+        let participating_stake = self
+            .latest_voter_stake
+            .iter()
+            .filter(|voter_stake| voter_stake.height == height)
+            .last()
+            .map(|voter_stake| voter_stake.participating_stake);
+
+        Some(BlockDetailPage {
+            block: clone_block_detail(block),
+            voters,
+            missing_voters: self.missing_voters(height),
+            participating_stake,
+        })
     }
 
-    if let Err(err) = voters_sender.send(voters_bitvec).await {
-        // We have an error that prevents us from continuing
-        return Err(ProcessLeafError::VotersSendError(err));
+    /// [voter_count_histogram] maps each number of voters to the number of
+    /// retained voters that had that many voters.
+    pub fn voter_count_histogram(&self) -> BTreeMap<usize, usize> {
+        self.normalized_voters()
+            .iter()
+            .fold(BTreeMap::new(), |mut histogram, voters| {
+                *histogram.entry(voters.count_ones()).or_insert(0) += 1;
+                histogram
+            })
     }
 
-    Ok(())
-}
+    /// [voters_for_height] returns the voters that were recorded for the
+    /// retained block with the given height.  Blocks and voters are recorded
+    /// together, so the voters are aligned with the blocks from the most
+    /// recent entry backwards.
+    pub fn voters_for_height(&self, height: u64) -> Option<&BitVec<u16>> {
+        let (block_index, _) = self
+            .latest_blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block.height == height)
+            .last()?;
 
-/// [ProcessLeafStreamTask] represents the task that is responsible for
-/// processing a stream of incoming [Leaf]s.
-pub struct ProcessLeafStreamTask {
-    pub task_handle: Option<JoinHandle<()>>,
-}
-
-impl ProcessLeafStreamTask {
-    /// [new] creates a new [ProcessLeafStreamTask] that will process a stream
-    /// of incoming [Leaf]s.
-    ///
-    /// Calling this function will create an asynchronous task that will start
-    /// processing immediately. The handle for the task will be stored within
-    /// the returned structure.
-    pub fn new<S, K1, K2>(
-        leaf_receiver: S,
-        data_state: Arc<RwLock<DataState>>,
-        block_detail_sender: K1,
-        voters_sender: K2,
-    ) -> Self
-    where
-        S: Stream<Item = Leaf<SeqTypes>> + Send + Sync + Unpin + 'static,
-        K1: Sink<BlockDetail<SeqTypes>, Error = SendError> + Clone + Send + Sync + Unpin + 'static,
-        K2: Sink<BitVec<u16>, Error = SendError> + Clone + Send + Sync + Unpin + 'static,
-    {
-        let task_handle = async_std::task::spawn(Self::process_leaf_stream(
-            leaf_receiver,
-            data_state.clone(),
-            block_detail_sender,
-            voters_sender,
-        ));
+        self.voters_at(block_index)
+    }
 
-        Self {
-            task_handle: Some(task_handle),
+    /// [voter_public_keys] resolves the given voters to the public keys of
+    /// the nodes that voted.  Before any identities are known, the voters
+    /// are recorded in stake table order, see [compute_voters_bitvec], so they
+    /// are resolved against the stake table for the voter attribution version
+    /// instead.
+    fn voter_public_keys(&self, voters: &BitVec<u16>) -> HashSet<BLSPubKey> {
+        if self.node_identity.is_empty() {
+            return self
+                .stake_table
+                .try_iter(self.voter_attribution_version)
+                .map_or(HashSet::new(), |into_iter| {
+                    zip(voters.iter().by_vals(), into_iter)
+                        .filter(|(voted, _)| *voted)
+                        .map(|(_, (key, _, _))| key)
+                        .collect()
+                });
         }
+
+        zip(voters.iter().by_vals(), self.node_identity.iter())
+            .filter(|(voted, _)| *voted)
+            .map(|(_, node_identity)| *node_identity.public_key())
+            .collect()
     }
 
-    /// [process_leaf_stream] allows for the consumption of a [Stream] when
-    /// attempting to process new incoming [Leaf]s.
-    async fn process_leaf_stream<S, BDSink, BVSink>(
-        mut stream: S,
-        data_state: Arc<RwLock<DataState>>,
-        block_sender: BDSink,
-        voters_senders: BVSink,
-    ) where
-        S: Stream<Item = Leaf<SeqTypes>> + Unpin,
-        Header: BlockHeader<SeqTypes> + QueryableHeader<SeqTypes> + ExplorerHeader<SeqTypes>,
-        Payload: BlockPayload<SeqTypes>,
-        BDSink: Sink<BlockDetail<SeqTypes>, Error = SendError> + Clone + Unpin,
-        BVSink: Sink<BitVec<u16>, Error = SendError> + Clone + Unpin,
-    {
-        loop {
-            let leaf_result = stream.next().await;
-            let leaf = if let Some(leaf) = leaf_result {
-                leaf
-            } else {
-                // We have reached the end of the stream
-                tracing::error!("process leaf stream: end of stream reached for leaf stream.");
-                return;
-            };
+    /// [block_at] returns the retained block at the given index, where the
+    /// oldest retained block is at index zero.
+    pub fn block_at(&self, index: usize) -> Option<&BlockDetail<SeqTypes>> {
+        self.latest_blocks.get(index)
+    }
 
-            if let Err(err) = process_incoming_leaf(
-                leaf,
-                data_state.clone(),
-                block_sender.clone(),
-                voters_senders.clone(),
-            )
-            .await
-            {
-                // We have an error that prevents us from continuing
-                tracing::error!("process leaf stream: error processing leaf: {}", err);
+    /// [voters_at] returns the voters for the retained block at the given
+    /// index, as returned by [block_at](Self::block_at).  The voters are
+    /// aligned with the blocks from the most recent entry backwards, so
+    /// [None] is returned if the voters for the block are not retained.
+    pub fn voters_at(&self, index: usize) -> Option<&BitVec<u16>> {
+        let blocks_len = self.latest_blocks.len();
+        if index >= blocks_len {
+            return None;
+        }
 
-                // At the moment, all underlying errors are due to `SendError`
-                // which will ultimately mean that further processing attempts
-                // will fail, and be fruitless.
-                match err {
-                    ProcessLeafError::BlockSendError(_) => {
-                        panic!("ProcessLeafStreamTask: process_incoming_leaf failed, underlying sink is closed, blocks will stagnate: {}", err)
-                    }
-                    ProcessLeafError::VotersSendError(_) => {
-                        panic!("ProcessLeafStreamTask: process_incoming_leaf failed, underlying sink is closed, voters will stagnate: {}", err)
-                    }
-                }
-            }
+        let blocks_from_back = blocks_len - index;
+        let voters_len = self.latest_voters.len();
+        if blocks_from_back > voters_len {
+            return None;
         }
+
+        self.latest_voters.get(voters_len - blocks_from_back)
     }
-}
 
-/// [Drop] implementation for [ProcessLeafStreamTask] that will cancel the
-/// task if it is dropped.
-impl Drop for ProcessLeafStreamTask {
-    fn drop(&mut self) {
-        let task_handle = self.task_handle.take();
-        if let Some(task_handle) = task_handle {
-            async_std::task::block_on(task_handle.cancel());
+    /// [voter_churn] compares the voters of each pair of consecutive retained
+    /// blocks.  For every block whose voters, and those of the block before
+    /// it, are retained, the height of the block is returned along with the
+    /// number of nodes that started voting, and the number of nodes that
+    /// stopped voting, relative to the previous block.
+    pub fn voter_churn(&self) -> Vec<(u64, usize, usize)> {
+        let len = self.node_identity.len();
+        let normalize = |voters: &BitVec<u16>| {
+            let mut voters = voters.clone();
+            voters.resize(len.max(voters.len()), false);
+            voters
+        };
+
+        (1..self.latest_blocks.len())
+            .filter_map(|index| {
+                let block = self.block_at(index)?;
+                let previous = normalize(self.voters_at(index - 1)?);
+                let current = normalize(self.voters_at(index)?);
+
+                let joined = current
+                    .iter_ones()
+                    .filter(|node_index| !previous.get(*node_index).is_some_and(|bit| *bit))
+                    .count();
+                let dropped = previous
+                    .iter_ones()
+                    .filter(|node_index| !current.get(*node_index).is_some_and(|bit| *bit))
+                    .count();
+
+                Some((block.height, joined, dropped))
+            })
+            .collect()
+    }
+
+    /// [voter_similarity] computes the Jaccard index of the sets of nodes
+    /// that voted for the retained blocks at the two given heights.  If
+    /// neither block has any voters, the sets are considered identical.
+    ///
+    /// Returns [None] if either of the heights is not retained.
+    pub fn voter_similarity(&self, height_a: u64, height_b: u64) -> Option<f64> {
+        let voters_a = self
+            .voters_for_height(height_a)?
+            .iter_ones()
+            .collect::<std::collections::HashSet<_>>();
+        let voters_b = self
+            .voters_for_height(height_b)?
+            .iter_ones()
+            .collect::<std::collections::HashSet<_>>();
+
+        let union = voters_a.union(&voters_b).count();
+        if union == 0 {
+            return Some(1.0);
         }
+
+        let intersection = voters_a.intersection(&voters_b).count();
+        Some(intersection as f64 / union as f64)
     }
-}
 
-/// [ProcessNodeIdentityError] represents the error that can occur when processing
-/// a [NodeIdentity].
-#[derive(Debug)]
-pub enum ProcessNodeIdentityError {
-    SendError(SendError),
-}
+    pub fn stake_table(&self) -> &StakeTable<BLSPubKey, StateVerKey, CircuitField> {
+        &self.stake_table
+    }
 
-impl std::fmt::Display for ProcessNodeIdentityError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ProcessNodeIdentityError::SendError(err) => {
-                write!(f, "error sending node identity to sender: {}", err)
+    pub fn node_identity(&self) -> impl Iterator<Item = &NodeIdentity> {
+        self.node_identity.iter()
+    }
+
+    /// [duplicate_location_nodes] groups the public keys of the nodes that
+    /// share an identical name and [LocationDetails], which may indicate that
+    /// a node has been accidentally cloned.  Nodes without a location are not
+    /// considered, and only groups of more than one node are returned, in
+    /// the order of the [NodeIdentity]s.
+    pub fn duplicate_location_nodes(&self) -> Vec<Vec<BLSPubKey>> {
+        // LocationDetails contains floating point coordinates, so it cannot
+        // be hashed, and the groups are found by comparison instead.
+        let mut groups: Vec<(&NodeIdentity, Vec<BLSPubKey>)> = Vec::new();
+        for node_identity in self.node_identity.iter() {
+            if node_identity.location().is_none() {
+                continue;
+            }
+
+            match groups.iter_mut().find(|(representative, _)| {
+                representative.location() == node_identity.location()
+                    && representative.name() == node_identity.name()
+            }) {
+                Some((_, keys)) => keys.push(*node_identity.public_key()),
+                None => groups.push((node_identity, vec![*node_identity.public_key()])),
             }
         }
+
+        groups
+            .into_iter()
+            .map(|(_, keys)| keys)
+            .filter(|keys| keys.len() > 1)
+            .collect()
     }
-}
 
-impl std::error::Error for ProcessNodeIdentityError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        match self {
-            ProcessNodeIdentityError::SendError(err) => Some(err),
+    /// [active_node_identity] returns the [NodeIdentity]s of the nodes that
+    /// have not been tombstoned.
+    pub fn active_node_identity(&self) -> impl Iterator<Item = &NodeIdentity> {
+        self.node_identity
+            .iter()
+            .filter(|node_identity| !self.is_tombstoned(node_identity.public_key()))
+    }
+
+    /// [tombstone_node] marks the node with the given public key as removed.
+    /// As an alternative to removing its [NodeIdentity], which would
+    /// misalign every retained voters [BitVec], the [NodeIdentity] is kept in
+    /// place, and the node is excluded from the active set instead.  A node
+    /// that later announces its [NodeIdentity] again becomes active again.
+    ///
+    /// Returns false if the public key does not belong to a known
+    /// [NodeIdentity].
+    pub fn tombstone_node(&mut self, key: &BLSPubKey) -> bool {
+        if !self
+            .node_identity
+            .iter()
+            .any(|node_identity| node_identity.public_key() == key)
+        {
+            return false;
         }
+
+        self.invalidate_network_summary();
+        self.tombstoned_nodes.insert(*key);
+        true
     }
-}
 
-impl From<SendError> for ProcessNodeIdentityError {
-    fn from(err: SendError) -> Self {
-        ProcessNodeIdentityError::SendError(err)
+    pub fn is_tombstoned(&self, key: &BLSPubKey) -> bool {
+        self.tombstoned_nodes.contains(key)
     }
-}
 
-/// [process_incoming_node_identity] is a helper function that will process an
-/// incoming [NodeIdentity] and update the [DataState] with the new information.
-/// Additionally, the [NodeIdentity] will be sent to the [Sink] so that it can
-/// be processed for real-time considerations.
-async fn process_incoming_node_identity<NISink>(
-    node_identity: NodeIdentity,
-    data_state: Arc<RwLock<DataState>>,
-    mut node_identity_sender: NISink,
-) -> Result<(), ProcessNodeIdentityError>
-where
-    NISink: Sink<NodeIdentity, Error = SendError> + Unpin,
-{
-    let mut data_state_write_lock_guard = data_state.write().await;
-    data_state_write_lock_guard.add_node_identity(node_identity.clone());
-    node_identity_sender.send(node_identity).await?;
+    pub fn recent_leaves(&self) -> impl Iterator<Item = &Leaf<SeqTypes>> {
+        self.recent_leaves.iter()
+    }
 
-    Ok(())
-}
+    pub fn recent_leaves_capacity(&self) -> usize {
+        self.recent_leaves.capacity()
+    }
 
-/// [ProcessNodeIdentityStreamTask] represents the task that is responsible for
-/// processing a stream of incoming [NodeIdentity]s and updating the [DataState]
-/// with the new information.
-pub struct ProcessNodeIdentityStreamTask {
-    pub task_handle: Option<JoinHandle<()>>,
-}
+    /// [set_recent_leaves_capacity] configures the number of full [Leaf]s
+    /// that will be retained.  A capacity of zero disables the retention of
+    /// [Leaf]s entirely.  If the capacity shrinks, the oldest [Leaf]s are
+    /// discarded.
+    pub fn set_recent_leaves_capacity(&mut self, capacity: usize) {
+        self.recent_leaves.set_capacity(capacity);
+    }
 
-impl ProcessNodeIdentityStreamTask {
-    /// [new] creates a new [ProcessNodeIdentityStreamTask] that will process a
-    /// stream of incoming [NodeIdentity]s.
-    ///
-    /// Calling this function will create an asynchronous task that will start
-    /// processing immediately. The handle for the task will be stored within
-    /// the returned structure.
-    pub fn new<S, K>(
-        node_identity_receiver: S,
-        data_state: Arc<RwLock<DataState>>,
-        node_identity_sender: K,
-    ) -> Self
-    where
-        S: Stream<Item = NodeIdentity> + Send + Sync + Unpin + 'static,
-        K: Sink<NodeIdentity, Error = SendError> + Clone + Send + Sync + Unpin + 'static,
-    {
-        let task_handle = async_std::task::spawn(Self::process_node_identity_stream(
-            node_identity_receiver,
-            data_state.clone(),
-            node_identity_sender,
-        ));
+    pub fn archived_blocks(&self) -> impl Iterator<Item = &BlockDetail<SeqTypes>> {
+        self.archived_blocks.iter()
+    }
 
-        Self {
-            task_handle: Some(task_handle),
-        }
+    pub fn archived_blocks_capacity(&self) -> usize {
+        self.archived_blocks.capacity()
     }
 
-    /// [process_node_identity_stream] allows for the consumption of a [Stream] when
-    /// attempting to process new incoming [NodeIdentity]s.
-    /// This function will process the incoming [NodeIdentity] and update the
-    /// [DataState] with the new information.
-    /// Additionally, the [NodeIdentity] will be sent to the [Sink] so that it can
-    /// be processed for real-time considerations.
-    async fn process_node_identity_stream<S, NISink>(
-        mut stream: S,
-        data_state: Arc<RwLock<DataState>>,
-        node_identity_sender: NISink,
-    ) where
-        S: Stream<Item = NodeIdentity> + Unpin,
-        NISink: Sink<NodeIdentity, Error = SendError> + Clone + Unpin,
-    {
-        loop {
-            let node_identity_result = stream.next().await;
-            let node_identity = if let Some(node_identity) = node_identity_result {
-                node_identity
-            } else {
-                // We have reached the end of the stream
-                tracing::info!(
-                    "process node identity stream: end of stream reached for node identity stream."
-                );
-                return;
-            };
+    /// [set_archived_blocks_capacity] configures the number of blocks that
+    /// will be retained within the archive.  A capacity of zero disables the
+    /// archive entirely.
+    pub fn set_archived_blocks_capacity(&mut self, capacity: usize) {
+        self.archived_blocks.set_capacity(capacity);
 
-            if let Err(err) = process_incoming_node_identity(
-                node_identity,
-                data_state.clone(),
-                node_identity_sender.clone(),
-            )
-            .await
-            {
-                // We have an error that prevents us from continuing
-                tracing::error!(
-                    "process node identity stream: error processing node identity: {}",
-                    err
-                );
+        let front_sequence = self.archived_front_sequence();
+        self.archived_block_index
+            .retain(|_, sequence| *sequence >= front_sequence);
+    }
 
-                // The only underlying class of errors that can be returned from
-                // `process_incoming_node_identity` are due to `SendError` which
-                // will ultimately mean that further processing attempts will fail
-                // and be fruitless.
-                panic!("ProcessNodeIdentityStreamTask: process_incoming_node_identity failed, underlying sink is closed, node identities will stagnate: {}", err);
+    /// [archived_block_by_height] returns the archived block with the given
+    /// height, if it is still retained within the archive.
+    pub fn archived_block_by_height(&self, height: u64) -> Option<&BlockDetail<SeqTypes>> {
+        let sequence = *self.archived_block_index.get(&height)?;
+        let position = sequence - self.archived_front_sequence();
+        self.archived_blocks.get(position as usize)
+    }
+
+    /// [archived_front_sequence] returns the sequence number of the oldest
+    /// block still retained within the archive.
+    fn archived_front_sequence(&self) -> u64 {
+        self.archived_blocks_pushed - self.archived_blocks.len() as u64
+    }
+
+    /// [archive_block] adds the given block to the archive, and keeps the
+    /// height index consistent with any block that is evicted as a result.
+    fn archive_block(&mut self, block: &BlockDetail<SeqTypes>) {
+        let front_sequence = self.archived_front_sequence();
+        if let Some(evicted) = self.archived_blocks.push_back(clone_block_detail(block)) {
+            // The index may already refer to a more recent block with the
+            // same height, in which case it must be left alone.
+            if self.archived_block_index.get(&evicted.height) == Some(&front_sequence) {
+                self.archived_block_index.remove(&evicted.height);
             }
         }
+
+        self.archived_block_index
+            .insert(block.height, self.archived_blocks_pushed);
+        self.archived_blocks_pushed += 1;
     }
-}
 
-/// [Drop] implementation for [ProcessNodeIdentityStreamTask] that will cancel
-/// the task if it is dropped.
-impl Drop for ProcessNodeIdentityStreamTask {
-    fn drop(&mut self) {
-        let task_handle = self.task_handle.take();
-        if let Some(task_handle) = task_handle {
-            async_std::task::block_on(task_handle.cancel());
+    pub fn archive_sampling_stride(&self) -> u64 {
+        self.archive_sampling_stride
+    }
+
+    /// [set_archive_sampling_stride] configures the archive to only store
+    /// every `stride`-th block, by height.  A stride of zero is treated as a
+    /// stride of one, which archives every block.
+    pub fn set_archive_sampling_stride(&mut self, stride: u64) {
+        self.archive_sampling_stride = stride.max(1);
+    }
+
+    pub fn latest_blocks_capacity(&self) -> usize {
+        self.latest_blocks.capacity()
+    }
+
+    /// [set_latest_blocks_capacity] configures the number of the most recent
+    /// blocks that will be retained.  If the capacity shrinks, the oldest
+    /// blocks are discarded.  A capacity of zero is treated as one.
+    pub fn set_latest_blocks_capacity(&mut self, capacity: usize) {
+        self.invalidate_network_summary();
+        let capacity = retained_capacity("latest blocks", capacity);
+        for block in self.latest_blocks.set_capacity(capacity) {
+            self.send_evicted_block(block);
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::{DataState, ProcessLeafStreamTask};
-    use crate::service::data_state::{
-        LocationDetails, NodeIdentity, ProcessNodeIdentityStreamTask,
-    };
-    use async_std::{prelude::FutureExt, sync::RwLock};
-    use espresso_types::{
-        v0_3::ChainConfig, BlockMerkleTree, FeeMerkleTree, Leaf, NodeState, ValidatedState,
-    };
-    use futures::{channel::mpsc, SinkExt, StreamExt};
-    use hotshot_types::{signature_key::BLSPubKey, traits::signature_key::SignatureKey};
-    use std::{sync::Arc, time::Duration};
-    use url::Url;
+    pub fn retention_window(&self) -> Option<Duration> {
+        self.retention_window
+    }
 
-    #[async_std::test]
-    async fn test_process_leaf_error_debug() {
-        let (mut sender, receiver) = mpsc::channel(1);
-        // deliberately close the receiver.
-        drop(receiver);
+    /// [set_retention_window] configures the time based retention of the
+    /// latest blocks, in addition to their capacity.  Passing [None] disables
+    /// the time based retention.  Blocks that are outside of the new window
+    /// are evicted immediately.
+    pub fn set_retention_window(&mut self, retention_window: Option<Duration>) {
+        self.invalidate_network_summary();
+        self.retention_window = retention_window;
+        self.evict_expired_blocks();
+    }
+
+    /// [evict_expired_blocks] evicts the oldest retained blocks whose time is
+    /// further than the retention window behind the newest retained block
+    /// time.  The newest time is taken across all of the retained blocks, so
+    /// that a block with an out of order timestamp cannot pull the window
+    /// backwards.  Blocks are only ever evicted from the front, in order to
+    /// keep the blocks aligned with the voters, so an expired block that is
+    /// preceded by one that has not expired is retained until it reaches the
+    /// front.
+    fn evict_expired_blocks(&mut self) {
+        let Some(retention_window) = self.retention_window else {
+            return;
+        };
+
+        let Some(newest_time) = self.latest_blocks.iter().map(|block| block.time.0).max() else {
+            return;
+        };
+
+        while self
+            .latest_blocks
+            .front()
+            .is_some_and(|block| newest_time - block.time.0 > retention_window)
+        {
+            if let Some(evicted) = self.latest_blocks.pop_front() {
+                self.send_evicted_block(evicted);
+            }
+        }
+
+        self.trim_expired_voters();
+    }
+
+    /// [trim_expired_voters] discards the oldest retained voters and voter
+    /// stake snapshots in excess of the number of retained blocks, when a
+    /// retention window is configured.  The voters are aligned with the
+    /// blocks from the most recent entry backwards, so the discarded voters
+    /// belong to blocks that have expired, and would otherwise still be
+    /// accounted for by the statistics that only consider the voters.
+    fn trim_expired_voters(&mut self) {
+        if self.retention_window.is_none() {
+            return;
+        }
+
+        let retained_blocks = self.latest_blocks.len();
+        while self.latest_voters.len() > retained_blocks {
+            self.latest_voters.pop_front();
+        }
+        while self.latest_voter_stake.len() > retained_blocks {
+            self.latest_voter_stake.pop_front();
+        }
+    }
+
+    /// [set_evicted_block_sender] configures the [Sender] that will receive
+    /// every block that is evicted from the latest blocks.  Passing [None]
+    /// disables the notification.
+    pub fn set_evicted_block_sender(&mut self, sender: Option<Sender<BlockDetail<SeqTypes>>>) {
+        self.evicted_block_sender = sender;
+    }
+
+    /// [send_evicted_block] hands the given evicted block to the evicted
+    /// block [Sender], if one has been configured.  This does not block, so
+    /// if the receiver is unable to keep up, the block is dropped and a
+    /// warning is emitted.  The warnings are rate limited, as a persistently
+    /// full receiver would otherwise produce one for every block.
+    fn send_evicted_block(&mut self, block: BlockDetail<SeqTypes>) {
+        let Some(sender) = self.evicted_block_sender.as_mut() else {
+            return;
+        };
+
+        let height = block.height;
+        if let Err(err) = sender.try_send(block) {
+            if let Some(failures) = self.evicted_block_send_failures.record(Instant::now()) {
+                tracing::warn!(
+                    "unable to send {} evicted block(s) since the last warning, most recently at height {}: {}",
+                    failures,
+                    height,
+                    err
+                );
+            }
+        }
+    }
+
+    /// [set_anomaly_event_sender] configures the [Sender] that will receive
+    /// an [AnomalyEvent] for every anomaly that is detected.  Passing [None]
+    /// disables the notification.
+    pub fn set_anomaly_event_sender(&mut self, sender: Option<Sender<AnomalyEvent>>) {
+        self.anomaly_event_sender = sender;
+    }
+
+    /// [send_anomaly_event] hands the given [AnomalyEvent] to the anomaly
+    /// event [Sender], if one has been configured.  Like
+    /// [send_evicted_block], this does not block, and the failures to send
+    /// are warned about at a limited rate.
+    fn send_anomaly_event(&mut self, event: AnomalyEvent) {
+        let Some(sender) = self.anomaly_event_sender.as_mut() else {
+            return;
+        };
+
+        if let Err(err) = sender.try_send(event) {
+            if let Some(failures) = self.anomaly_event_send_failures.record(Instant::now()) {
+                tracing::warn!(
+                    "unable to send {} anomaly event(s) since the last warning: {}",
+                    failures,
+                    err
+                );
+            }
+        }
+    }
+
+    pub fn latest_voters_capacity(&self) -> usize {
+        self.latest_voters.capacity()
+    }
+
+    /// [set_latest_voters_capacity] configures the number of the most recent
+    /// voters that will be retained.  If the capacity shrinks, the oldest
+    /// voters are discarded.  A capacity of zero is treated as one.
+    pub fn set_latest_voters_capacity(&mut self, capacity: usize) {
+        self.invalidate_network_summary();
+        self.latest_voters
+            .set_capacity(retained_capacity("latest voters", capacity));
+    }
+
+    pub fn latest_voter_stake_capacity(&self) -> usize {
+        self.latest_voter_stake.capacity()
+    }
+
+    /// [set_latest_voter_stake_capacity] configures the number of the most
+    /// recent voter stake snapshots that will be retained.  If the capacity
+    /// shrinks, the oldest snapshots are discarded.  A capacity of zero is
+    /// treated as one.
+    pub fn set_latest_voter_stake_capacity(&mut self, capacity: usize) {
+        self.latest_voter_stake
+            .set_capacity(retained_capacity("latest voter stake", capacity));
+    }
+
+    pub fn cumulative_blocks(&self) -> u64 {
+        self.cumulative_blocks
+    }
+
+    pub fn cumulative_transactions(&self) -> u64 {
+        self.cumulative_transactions
+    }
+
+    pub fn highest_counted_height(&self) -> Option<u64> {
+        self.highest_counted_height
+    }
+
+    /// [update_cumulative_counters] accounts for the given block within the
+    /// lifetime counters.  Blocks at or below the highest height that has
+    /// already been counted are ignored, which keeps the counters accurate
+    /// when overlapping ranges of blocks are replayed.
+    fn update_cumulative_counters(&mut self, block: &BlockDetail<SeqTypes>) {
+        if let Some(highest_counted_height) = self.highest_counted_height {
+            if block.height <= highest_counted_height {
+                return;
+            }
+        }
+
+        self.cumulative_blocks += 1;
+        self.cumulative_transactions += block.num_transactions;
+        self.highest_counted_height = Some(block.height);
+    }
+
+    pub fn duplicate_leaves(&self) -> u64 {
+        self.duplicate_leaves
+    }
+
+    pub fn fork_events(&self) -> impl Iterator<Item = &ForkEvent> {
+        self.fork_events.iter()
+    }
+
+    /// [detect_fork] determines whether the given block conflicts with a
+    /// retained block, that is, whether a block at the same height has
+    /// already been stored with a different hash.
+    pub fn detect_fork(&self, block: &BlockDetail<SeqTypes>) -> Option<ForkEvent> {
+        self.latest_blocks
+            .iter()
+            .find(|stored| stored.height == block.height && stored.hash != block.hash)
+            .map(|stored| ForkEvent {
+                height: block.height,
+                hash_a: stored.hash,
+                hash_b: block.hash,
+            })
+    }
+
+    pub fn fee_recipient_identity(&self) -> &HashMap<FeeAccount, BLSPubKey> {
+        &self.fee_recipient_identity
+    }
+
+    /// [set_fee_recipient_identity] replaces the entire mapping of fee
+    /// recipient accounts to node public keys.  This is expected to be
+    /// loaded from configuration.
+    pub fn set_fee_recipient_identity(
+        &mut self,
+        fee_recipient_identity: HashMap<FeeAccount, BLSPubKey>,
+    ) {
+        self.fee_recipient_identity = fee_recipient_identity;
+    }
+
+    /// [add_fee_recipient_identity] records that the given fee recipient
+    /// account belongs to the node with the given public key.
+    pub fn add_fee_recipient_identity(&mut self, recipient: FeeAccount, public_key: BLSPubKey) {
+        self.fee_recipient_identity.insert(recipient, public_key);
+    }
+
+    /// [resolve_recipient_identity] attempts to find the [NodeIdentity] that
+    /// the given fee recipient belongs to.
+    pub fn resolve_recipient_identity(&self, recipient: &FeeAccount) -> Option<&NodeIdentity> {
+        let public_key = self.fee_recipient_identity.get(recipient)?;
+        self.node_identity
+            .iter()
+            .find(|node_identity| node_identity.public_key() == public_key)
+    }
+
+    /// [resolve_recipient_name] attempts to resolve the given fee recipient
+    /// to the name of the node that it belongs to.  If there is no known
+    /// mapping, or the node has not supplied a name, [None] is returned, and
+    /// the raw address is expected to be displayed instead.
+    pub fn resolve_recipient_name(&self, recipient: &FeeAccount) -> Option<String> {
+        self.resolve_recipient_identity(recipient)?.name().clone()
+    }
+
+    /// [network_summary] returns a [NetworkSummary] of the currently retained
+    /// information.  The summary is cached until the retained information
+    /// changes, or a block at a new height is retained.
+    pub fn network_summary(&self) -> NetworkSummary {
+        let latest_block_height = self.latest_block_height();
+        let mut cache = self.network_summary_cache.lock().unwrap_or_else(|err| {
+            self.network_summary_cache.clear_poison();
+            recover_poisoned("network summary cache", err)
+        });
+
+        match cache.as_ref() {
+            Some(summary) if summary.latest_block_height == latest_block_height => summary.clone(),
+            _ => {
+                let summary = self.compute_network_summary();
+                *cache = Some(summary.clone());
+                summary
+            }
+        }
+    }
+
+    /// [invalidate_network_summary] discards the cached [NetworkSummary].
+    fn invalidate_network_summary(&mut self) {
+        *self
+            .network_summary_cache
+            .get_mut()
+            .unwrap_or_else(|err| recover_poisoned("network summary cache", err)) = None;
+        self.network_summary_cache.clear_poison();
+    }
+
+    /// [compute_network_summary] computes a [NetworkSummary] from the
+    /// currently retained information.
+    fn compute_network_summary(&self) -> NetworkSummary {
+        let mut latest_block_height = None;
+        let mut num_blocks = 0u64;
+        let mut total_size = 0u64;
+        let mut first_block_time: Option<OffsetDateTime> = None;
+        let mut last_block_time: Option<OffsetDateTime> = None;
+        let mut num_transactions_since_first_block = 0u64;
+
+        for block in self.latest_blocks.iter() {
+            if first_block_time.is_some() {
+                // The transactions within the first block were not produced
+                // within the time span that we are measuring.
+                num_transactions_since_first_block += block.num_transactions;
+            } else {
+                first_block_time = Some(block.time.0);
+            }
+
+            num_blocks += 1;
+            total_size += block.size;
+            last_block_time = Some(block.time.0);
+            latest_block_height = Some(block.height);
+        }
+
+        let average_block_size = if num_blocks == 0 {
+            None
+        } else {
+            Some(total_size as f64 / num_blocks as f64)
+        };
+
+        let estimated_tps = match (first_block_time, last_block_time) {
+            (Some(first), Some(last)) if last > first => {
+                Some(num_transactions_since_first_block as f64 / (last - first).as_seconds_f64())
+            }
+            _ => None,
+        };
+
+        // Voters are tracked by their index within the node identity list,
+        // so we can determine the distinct voters by combining all of the
+        // BitVecs together.
+        let distinct_voters = self
+            .latest_voters
+            .iter()
+            .fold(BitVec::<u16>::new(), |mut acc, voters| {
+                if acc.len() < voters.len() {
+                    acc.resize(voters.len(), false);
+                }
+                for index in voters.iter_ones() {
+                    acc.set(index, true);
+                }
+                acc
+            })
+            .count_ones();
+
+        let total_stake = self
+            .stake_table
+            .total_stake(self.voter_attribution_version)
+            .unwrap_or_default();
+
+        NetworkSummary {
+            latest_block_height,
+            known_node_count: self.active_node_identity().count(),
+            average_block_size,
+            estimated_tps,
+            distinct_voters,
+            total_stake,
+        }
+    }
+
+    /// [stake_table_json] renders the entries of the stake table for the
+    /// given [SnapshotVersion] as a JSON array of objects.  The keys are hex
+    /// encoded, and the stake is rendered as a decimal string in order to
+    /// avoid precision issues for JavaScript consumers.
+    pub fn stake_table_json(&self, version: SnapshotVersion) -> serde_json::Value {
+        let entries = match self.stake_table.try_iter(version) {
+            Ok(into_iter) => into_iter
+                .map(|(bls_key, stake, state_key)| {
+                    serde_json::json!({
+                        "bls_key": encode_hex(&bls_key),
+                        "stake": stake.to_string(),
+                        "state_key": encode_hex(&state_key),
+                    })
+                })
+                .collect(),
+            Err(_) => vec![],
+        };
+
+        serde_json::Value::Array(entries)
+    }
+
+    /// [latest_blocks_json] renders the retained blocks as a JSON array of
+    /// objects.  The block times are stored in UTC, but are rendered in the
+    /// given offset from UTC, in seconds.  See [render_timestamp] for how
+    /// invalid offsets are handled.
+    pub fn latest_blocks_json(&self, utc_offset_seconds: i32) -> serde_json::Value {
+        let blocks = self
+            .latest_blocks
+            .iter()
+            .map(|block| {
+                serde_json::json!({
+                    "height": block.height,
+                    "hash": block.hash.to_string(),
+                    "time": render_timestamp(&block.time, utc_offset_seconds),
+                    "num_transactions": block.num_transactions,
+                    "size": block.size,
+                })
+            })
+            .collect();
+
+        serde_json::Value::Array(blocks)
+    }
+
+    /// [grafana_timeseries_json] renders the retained blocks as a timeseries
+    /// for each of the given targets, in the shape that is expected by the
+    /// Grafana JSON datasource:
+    /// `[{ "target": ..., "datapoints": [[value, unix_timestamp_ms], ...] }]`.
+    /// See [GRAFANA_TIMESERIES_TARGETS] for the supported targets.
+    ///
+    /// The transactions per second of a block are measured against the
+    /// interval since the preceding retained block, so the oldest block, and
+    /// any block without a positive interval, has no datapoint.  Likewise,
+    /// only blocks with a retained [VoterStakeSnapshot] have a participation
+    /// datapoint.  An unknown target is rendered without any datapoints,
+    /// rather than failing the entire query.
+    pub fn grafana_timeseries_json(&self, targets: &[&str]) -> serde_json::Value {
+        let blocks = self.latest_blocks.iter().collect::<Vec<_>>();
+        let timestamp_ms = |block: &BlockDetail<SeqTypes>| {
+            (block.time.0.unix_timestamp_nanos() / 1_000_000) as i64
+        };
+
+        let participation_by_height = self
+            .latest_voter_stake
+            .iter()
+            .filter_map(|voter_stake| {
+                stake_fraction(voter_stake.participating_stake, voter_stake.total_stake)
+                    .map(|fraction| (voter_stake.height, fraction))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let timeseries = targets
+            .iter()
+            .map(|target| {
+                let datapoints: Vec<(f64, i64)> = match *target {
+                    "size" => blocks
+                        .iter()
+                        .copied()
+                        .map(|block| (block.size as f64, timestamp_ms(block)))
+                        .collect(),
+                    "tps" => blocks
+                        .windows(2)
+                        .filter_map(|pair| {
+                            let interval = (pair[1].time.0 - pair[0].time.0).as_seconds_f64();
+                            if interval <= 0.0 {
+                                return None;
+                            }
+
+                            Some((
+                                pair[1].num_transactions as f64 / interval,
+                                timestamp_ms(pair[1]),
+                            ))
+                        })
+                        .collect(),
+                    "participation" => blocks
+                        .iter()
+                        .copied()
+                        .filter_map(|block| {
+                            participation_by_height
+                                .get(&block.height)
+                                .map(|fraction| (*fraction, timestamp_ms(block)))
+                        })
+                        .collect(),
+                    _ => {
+                        tracing::debug!("grafana timeseries: unknown target {}", target);
+                        vec![]
+                    }
+                };
+
+                serde_json::json!({
+                    "target": target,
+                    "datapoints": datapoints
+                        .into_iter()
+                        .map(|(value, timestamp_ms)| serde_json::json!([value, timestamp_ms]))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        serde_json::Value::Array(timeseries)
+    }
+
+    /// [debug_dump] renders everything that is retained within the
+    /// [DataState] as a single versioned JSON document, for attaching to
+    /// support tickets.
+    pub fn debug_dump(&self) -> serde_json::Value {
+        self.debug_dump_with_identities(false)
+    }
+
+    /// [anonymized_debug_dump] is the same as [debug_dump], except that the
+    /// node identities are reduced to their public keys.
+    pub fn anonymized_debug_dump(&self) -> serde_json::Value {
+        self.debug_dump_with_identities(true)
+    }
+
+    fn debug_dump_with_identities(&self, anonymize: bool) -> serde_json::Value {
+        let node_identities = self
+            .node_identity
+            .iter()
+            .map(|node_identity| {
+                let result = if anonymize {
+                    serde_json::to_value(NodeIdentity::from_public_key(*node_identity.public_key()))
+                } else {
+                    serde_json::to_value(node_identity)
+                };
+
+                result.unwrap_or(serde_json::Value::Null)
+            })
+            .collect::<Vec<_>>();
+
+        let voters = self
+            .normalized_voters()
+            .iter()
+            .map(|voters| voters.iter().by_vals().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        serde_json::json!({
+            "version": DEBUG_DUMP_VERSION,
+            "network_summary": serde_json::to_value(self.network_summary())
+                .unwrap_or(serde_json::Value::Null),
+            "blocks": self.latest_blocks_json(0),
+            "voters": voters,
+            "node_identities": node_identities,
+            "stake_table": self.stake_table_json(SnapshotVersion::Head),
+        })
+    }
+
+    /// [stake_of] returns the stake of the given public key within the stake
+    /// table for the given [SnapshotVersion].
+    ///
+    /// Returns [None] if the public key is not in the stake table, or if the
+    /// stake table entries for the version cannot be retrieved.
+    pub fn stake_of(&self, key: &BLSPubKey, version: SnapshotVersion) -> Option<U256> {
+        self.stake_table
+            .try_iter(version)
+            .ok()?
+            .find(|(entry_key, _, _)| entry_key == key)
+            .map(|(_, stake, _)| stake)
+    }
+
+    /// [validator_count] returns the number of entries in the stake table for
+    /// the given [SnapshotVersion], without collecting them.  A version whose
+    /// entries cannot be retrieved is treated as having no entries.
+    pub fn validator_count(&self, version: SnapshotVersion) -> usize {
+        self.stake_table.len(version).unwrap_or(0)
+    }
+
+    /// [approximate_memory_bytes] estimates the memory that is used by the
+    /// retained data.  The estimate is the number of retained elements
+    /// multiplied by a representative size for each element, along with the
+    /// heap allocations that grow with the chain, such as the voter bits.
+    /// It does not account for allocator overhead or spare capacity.
+    pub fn approximate_memory_bytes(&self) -> usize {
+        let block_bytes = |block: &BlockDetail<SeqTypes>| {
+            std::mem::size_of::<BlockDetail<SeqTypes>>()
+                + (block.proposer_id.len() + block.fee_recipient.len())
+                    * std::mem::size_of::<FeeAccount>()
+                + block.block_reward.len() * std::mem::size_of::<FeeAmount>()
+        };
+
+        let blocks = self
+            .latest_blocks
+            .iter()
+            .chain(self.archived_blocks.iter())
+            .map(block_bytes)
+            .sum::<usize>();
+
+        let voters = self
+            .latest_voters
+            .iter()
+            .map(|voters| {
+                std::mem::size_of::<BitVec<u16>>() + std::mem::size_of_val(voters.as_raw_slice())
+            })
+            .sum::<usize>();
+
+        let voter_stake = self.latest_voter_stake.len() * std::mem::size_of::<VoterStakeSnapshot>();
+        let recent_leaves = self.recent_leaves.len() * std::mem::size_of::<Leaf<SeqTypes>>();
+        let node_identities = self.node_identity.len() * std::mem::size_of::<NodeIdentity>();
+
+        let stake_table_entries = self
+            .stake_table
+            .try_iter(SnapshotVersion::Head)
+            .map_or(0, |into_iter| into_iter.count());
+        let stake_table = stake_table_entries
+            * (std::mem::size_of::<BLSPubKey>()
+                + std::mem::size_of::<U256>()
+                + std::mem::size_of::<StateVerKey>());
+
+        blocks + voters + voter_stake + recent_leaves + node_identities + stake_table
+    }
+
+    pub fn voter_attribution_version(&self) -> SnapshotVersion {
+        self.voter_attribution_version
+    }
+
+    /// [set_voter_attribution_version] configures the [SnapshotVersion] of
+    /// the stake table that is used to attribute voters.  This should only
+    /// be changed from [SnapshotVersion::LastEpochStart] for analysis of
+    /// epoch transitions.
+    pub fn set_voter_attribution_version(&mut self, version: SnapshotVersion) {
+        self.voter_attribution_version = version;
+        self.invalidate_network_summary();
+    }
+
+    /// [set_voter_attribution_source] configures the
+    /// [VoterAttributionSource] that the voters of every subsequently
+    /// processed [Leaf] are extracted with.
+    pub fn set_voter_attribution_source(
+        &mut self,
+        voter_attribution_source: Arc<dyn VoterAttributionSource>,
+    ) {
+        self.voter_attribution_source = voter_attribution_source;
+    }
+
+    /// [set_transaction_filter] configures the predicate that determines
+    /// which transactions of the processed leaves count toward the number of
+    /// transactions and the size of their blocks.  Passing [None] restores
+    /// the default of counting every transaction.  Blocks that have already
+    /// been processed are not affected.
+    pub fn set_transaction_filter(
+        &mut self,
+        transaction_filter: Option<Arc<dyn Fn(&Transaction) -> bool + Send + Sync>>,
+    ) {
+        self.transaction_filter = transaction_filter;
+    }
+
+    /// [total_stake] returns the total stake of the stake table for the
+    /// given [SnapshotVersion].
+    ///
+    /// Returns [None] if the stake table for the version cannot be
+    /// retrieved.
+    pub fn total_stake(&self, version: SnapshotVersion) -> Option<U256> {
+        self.stake_table.total_stake(version).ok()
+    }
+
+    pub fn running_aggregates(&self) -> &RunningAggregates {
+        &self.running_aggregates
+    }
+
+    /// [set_running_aggregates_smoothing] resets the running aggregates with
+    /// the given smoothing.  See [RunningAggregates::new].
+    pub fn set_running_aggregates_smoothing(&mut self, smoothing: f64) {
+        self.running_aggregates = RunningAggregates::new(smoothing);
+    }
+
+    pub fn voter_tracking_enabled(&self) -> bool {
+        self.voter_tracking_enabled
+    }
+
+    /// [set_voter_tracking_enabled] enables or disables the attribution of
+    /// voters for each processed leaf.  While disabled, nothing is sent to
+    /// the voters or voter stake [Sink]s, so their receivers may be dropped.
+    ///
+    /// The retained voters are paired with the retained blocks from the most
+    /// recent entry backwards, and blocks continue to be retained while voter
+    /// tracking is disabled.  So the retained voters and voter stake
+    /// snapshots are discarded whenever the setting changes, as they would
+    /// otherwise be attributed to the wrong blocks.
+    pub fn set_voter_tracking_enabled(&mut self, enabled: bool) {
+        if self.voter_tracking_enabled == enabled {
+            return;
+        }
+
+        self.invalidate_network_summary();
+        self.voter_tracking_enabled = enabled;
+        self.latest_voters.clear();
+        self.latest_voter_stake.clear();
+    }
+
+    pub fn blocklist_height(&mut self, height: u64) {
+        self.blocklisted_heights.insert(height);
+    }
+
+    pub fn unblocklist_height(&mut self, height: u64) {
+        self.blocklisted_heights.remove(&height);
+    }
+
+    pub fn blocklist_hash(&mut self, hash: BlockHash<SeqTypes>) {
+        self.blocklisted_hashes.insert(hash);
+    }
+
+    pub fn unblocklist_hash(&mut self, hash: &BlockHash<SeqTypes>) {
+        self.blocklisted_hashes.remove(hash);
+    }
+
+    /// [is_blocklisted] determines whether the given block matches either a
+    /// blocklisted height, or a blocklisted hash.
+    pub fn is_blocklisted(&self, block: &BlockDetail<SeqTypes>) -> bool {
+        self.blocklisted_heights.contains(&block.height)
+            || self.blocklisted_hashes.contains(&block.hash)
+    }
+
+    pub fn genesis_handled(&self) -> bool {
+        self.genesis_handled
+    }
+
+    /// [check_leaf_anomalies] emits warnings for a block that was decided
+    /// without any voters, or whose payload was unavailable.  Both are
+    /// expected for the genesis block, so it is only recorded as having been
+    /// handled instead.
+    fn check_leaf_anomalies(&mut self, height: u64, voters: &BitVec<u16>, payload_available: bool) {
+        if height == 0 {
+            self.genesis_handled = true;
+            return;
+        }
+
+        if voters.not_any() {
+            tracing::warn!("block at height {} was decided without any voters", height);
+            self.send_anomaly_event(AnomalyEvent::EmptyVoters { height });
+        }
+
+        if !payload_available {
+            tracing::warn!("payload is unavailable for the block at height {}", height);
+            self.send_anomaly_event(AnomalyEvent::PayloadUnavailable { height });
+        }
+    }
+
+    pub fn payload_fallback_count(&self) -> u64 {
+        self.payload_fallback_count
+    }
+
+    pub fn voters_beyond_stake_table(&self) -> u64 {
+        self.voters_beyond_stake_table
+    }
+
+    /// [compute_voters] attributes the voters of the Quorum Certificate for
+    /// the block at the given height, which are given in the order of the
+    /// stake table, to the known [NodeIdentity]s.  It also determines the
+    /// stake that participated in the Quorum Certificate.
+    fn compute_voters(
+        &mut self,
+        height: u64,
+        stake_table_voters_bit_vec: BitVec<u16>,
+    ) -> (BitVec<u16>, VoterStakeSnapshot) {
+        let voter_attribution_version = self.voter_attribution_version;
+        let stable_table_entries_vec = self.stake_table_entries(voter_attribution_version);
+        self.check_voters_against_stake_table(
+            &stake_table_voters_bit_vec,
+            stable_table_entries_vec.len(),
+        );
+
+        let total_stake = stable_table_entries_vec
+            .iter()
+            .fold(U256::zero(), |acc, (_, stake, _)| acc + *stake);
+
+        // We have a BitVec of voters who signed the QC.
+        // We can use this to determine the weight of the QC
+        let stake_table_entry_voter_participation_and_entries_pairs =
+            zip(stake_table_voters_bit_vec.clone(), stable_table_entries_vec);
+        let stake_table_entries_that_voted =
+            stake_table_entry_voter_participation_and_entries_pairs
+                .filter(|(bit_ref, _)| *bit_ref)
+                .map(|(_, entry)| entry)
+                .collect::<Vec<_>>();
+
+        let participating_stake = stake_table_entries_that_voted
+            .iter()
+            .fold(U256::zero(), |acc, (_, stake, _)| acc + *stake);
+
+        let voters_set: HashSet<BLSPubKey> = stake_table_entries_that_voted
+            .into_iter()
+            .map(|entry| {
+                // Alright this is our entry that we care about.
+                // In this case, we just want to determine who voted for this
+                // Leaf.
+
+                let (key, _, _): (BLSPubKey, _, _) = entry;
+                key
+            })
+            .collect();
+
+        let voter_stake_snapshot = VoterStakeSnapshot {
+            height,
+            participating_stake,
+            total_stake,
+        };
+
+        let voters_bitvec =
+            compute_voters_bitvec(&self.node_identity, &voters_set, stake_table_voters_bit_vec);
+
+        (voters_bitvec, voter_stake_snapshot)
+    }
+
+    /// [check_voters_against_stake_table] determines how many of the given
+    /// voters are set beyond the given length of the stake table.  Such
+    /// voters would otherwise be silently dropped when the voters are paired
+    /// with the stake table entries, so they are logged and counted instead.
+    fn check_voters_against_stake_table(
+        &mut self,
+        voters: &BitVec<u16>,
+        stake_table_len: usize,
+    ) -> usize {
+        let voters_beyond = voters
+            .get(stake_table_len..)
+            .map_or(0, |beyond| beyond.count_ones());
+
+        if voters_beyond > 0 {
+            self.voters_beyond_stake_table += voters_beyond as u64;
+            tracing::warn!(
+                "{} voters were set beyond the end of the stake table of length {}",
+                voters_beyond,
+                stake_table_len
+            );
+            self.send_anomaly_event(AnomalyEvent::VotersBeyondStakeTable {
+                voters_beyond,
+                stake_table_len,
+            });
+        }
+
+        voters_beyond
+    }
+
+    pub fn stake_table_iteration_failures(&self) -> u64 {
+        self.stake_table_iteration_failures
+    }
+
+    pub fn leaf_height_gaps(&self) -> u64 {
+        self.leaf_height_gaps
+    }
+
+    pub fn latest_ingestion_delay(&self) -> Option<Duration> {
+        self.latest_ingestion_delay
+    }
+
+    /// [record_leaf_annotation] records the delivery of the [AnnotatedLeaf]
+    /// with the given block height that is about to be processed at the
+    /// given time.  The sequence numbers of an [AnnotatedLeaf] are assigned
+    /// once the leaf has reached the leaf stream, so they cannot reveal the
+    /// leaves that were dropped before then.  Instead, the number of heights
+    /// that were skipped since the highest previously recorded height is
+    /// returned.  Leaves at or below that height, such as replayed leaves,
+    /// skip no heights.
+    pub fn record_leaf_annotation(
+        &mut self,
+        height: u64,
+        ingested_at: Instant,
+        now: Instant,
+    ) -> u64 {
+        let gap = match self.last_leaf_height {
+            Some(last_height) if height > last_height.saturating_add(1) => {
+                tracing::warn!(
+                    "{} leaves were skipped between heights {} and {}",
+                    height - last_height - 1,
+                    last_height,
+                    height
+                );
+                self.send_anomaly_event(AnomalyEvent::LeafHeightGap {
+                    from: last_height + 1,
+                    to: height - 1,
+                });
+                height - last_height - 1
+            }
+            _ => 0,
+        };
+
+        self.last_leaf_height = Some(
+            self.last_leaf_height
+                .map_or(height, |last| last.max(height)),
+        );
+        self.leaf_height_gaps += gap;
+        self.latest_ingestion_delay = Some(now.saturating_duration_since(ingested_at));
+        gap
+    }
+
+    /// [record_finalization_lag] records how long after its timestamp the
+    /// given block was observed at `observed_at`, and returns the recorded
+    /// lag.  A block whose timestamp is ahead of `observed_at` indicates clock
+    /// skew, and its lag is clamped to zero.
+    pub fn record_finalization_lag(
+        &mut self,
+        block: &BlockDetail<SeqTypes>,
+        observed_at: OffsetDateTime,
+    ) -> Duration {
+        let lag = observed_at - block.time.0;
+        let lag = if lag.is_negative() {
+            self.send_anomaly_event(AnomalyEvent::TimestampAhead {
+                height: block.height,
+            });
+            if let Some(occurrences) = self.negative_finalization_lags.record(Instant::now()) {
+                tracing::warn!(
+                    "{} block(s) observed before their timestamp since the last warning, most recently at height {} by {}",
+                    occurrences,
+                    block.height,
+                    -lag
+                );
+            }
+            Duration::ZERO
+        } else {
+            Duration::try_from(lag).unwrap_or(Duration::ZERO)
+        };
+
+        self.finalization_lags.push_back(lag);
+        lag
+    }
+
+    /// [average_finalization_lag] returns the average time between a block's
+    /// timestamp and the observation of its leaf, across the most recently
+    /// processed leaves.
+    pub fn average_finalization_lag(&self) -> Option<Duration> {
+        if self.finalization_lags.is_empty() {
+            return None;
+        }
+
+        let total = self.finalization_lags.iter().sum::<Duration>();
+        Some(total / self.finalization_lags.len() as u32)
+    }
+
+    /// [stake_table_entries] returns the entries of the stake table for the
+    /// given [SnapshotVersion].  A stake table that is valid, but empty,
+    /// results in no entries.  A failure to retrieve the entries also results
+    /// in no entries, but is logged and counted separately so that problems
+    /// with the stake table can be diagnosed.
+    fn stake_table_entries(
+        &mut self,
+        version: SnapshotVersion,
+    ) -> Vec<(BLSPubKey, U256, StateVerKey)> {
+        match self.stake_table.try_iter(version) {
+            Ok(into_iter) => into_iter.collect(),
+            Err(err) => {
+                self.stake_table_iteration_failures += 1;
+                tracing::warn!(
+                    "unable to retrieve stake table entries for {:?}: {:?}",
+                    version,
+                    err
+                );
+                vec![]
+            }
+        }
+    }
+
+    /// [validators_by_stake] returns the public keys and stakes of the stake
+    /// table entries for the given [SnapshotVersion], sorted by descending
+    /// stake.  Entries with equal stake are ordered by the bytes of their
+    /// public key, so that the order is stable.
+    ///
+    /// Returns no entries if the stake table entries for the version cannot
+    /// be retrieved.
+    pub fn validators_by_stake(&self, version: SnapshotVersion) -> Vec<(BLSPubKey, U256)> {
+        let mut validators = match self.stake_table.try_iter(version) {
+            Ok(into_iter) => into_iter
+                .map(|(key, stake, _)| (canonical_bytes(&key), key, stake))
+                .collect::<Vec<_>>(),
+            Err(_) => vec![],
+        };
+
+        validators.sort_by(|(bytes_a, _, stake_a), (bytes_b, _, stake_b)| {
+            stake_b.cmp(stake_a).then_with(|| bytes_a.cmp(bytes_b))
+        });
+
+        validators
+            .into_iter()
+            .map(|(_, key, stake)| (key, stake))
+            .collect()
+    }
+
+    /// [unknown_stake_table_entries] returns the public keys of the current
+    /// stake table entries that do not have a corresponding [NodeIdentity].
+    /// These are the validators that we have no information about at all.
+    pub fn unknown_stake_table_entries(&self) -> Vec<BLSPubKey> {
+        let current_identity_set = self
+            .node_identity
+            .iter()
+            .map(|node_identity| *node_identity.public_key())
+            .collect::<std::collections::HashSet<_>>();
+
+        match self.stake_table.try_iter(SnapshotVersion::Head) {
+            Ok(into_iter) => into_iter
+                .map(|(key, _, _)| key)
+                .filter(|key| !current_identity_set.contains(key))
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// [block_size_std_dev] computes the population standard deviation of the
+    /// sizes of the retained blocks.  The variance is accumulated using
+    /// Welford's online algorithm in order to remain numerically stable.
+    ///
+    /// Returns [None] if there are fewer than two retained blocks.
+    pub fn block_size_std_dev(&self) -> Option<f64> {
+        let (count, _, sum_of_squared_differences) = self.latest_blocks.iter().fold(
+            (0u64, 0f64, 0f64),
+            |(count, mean, sum_of_squared_differences), block| {
+                let count = count + 1;
+                let size = block.size as f64;
+                let delta = size - mean;
+                let mean = mean + delta / count as f64;
+                (
+                    count,
+                    mean,
+                    sum_of_squared_differences + delta * (size - mean),
+                )
+            },
+        );
+
+        if count < 2 {
+            return None;
+        }
+
+        Some((sum_of_squared_differences / count as f64).sqrt())
+    }
+
+    /// [ewma_block_size] computes the exponentially weighted moving average
+    /// of the sizes of the retained blocks, from oldest to newest.  Each
+    /// block is given a weight of `alpha`, and the average of the blocks
+    /// before it a weight of `1 - alpha`, so that the more recent blocks
+    /// have a larger effect on the result.
+    ///
+    /// Returns [None] if there are no retained blocks, or if `alpha` is not
+    /// within the range `(0, 1]`.
+    pub fn ewma_block_size(&self, alpha: f64) -> Option<f64> {
+        if alpha.is_nan() || alpha <= 0.0 || alpha > 1.0 {
+            tracing::warn!(
+                "ewma block size: smoothing factor {} is not within (0, 1]",
+                alpha
+            );
+            return None;
+        }
+
+        self.latest_blocks
+            .iter()
+            .map(|block| block.size as f64)
+            .fold(None, |average, size| match average {
+                None => Some(size),
+                Some(average) => Some(alpha * size + (1.0 - alpha) * average),
+            })
+    }
+
+    /// [busiest_block] returns the retained block with the most
+    /// transactions.  Ties are broken in favor of the most recent block.
+    ///
+    /// Returns [None] if there are no retained blocks.
+    pub fn busiest_block(&self) -> Option<&BlockDetail<SeqTypes>> {
+        // max_by_key returns the last maximum element, which is the most
+        // recent one.
+        self.latest_blocks
+            .iter()
+            .max_by_key(|block| block.num_transactions)
+    }
+
+    /// [top_blocks_by_transactions] returns up to `n` of the retained blocks
+    /// with the most transactions, in descending order of the number of
+    /// transactions.  As with [busiest_block], ties are broken in favor of the
+    /// most recent block.
+    pub fn top_blocks_by_transactions(&self, n: usize) -> Vec<&BlockDetail<SeqTypes>> {
+        // The blocks are sorted from the most recent, so that the stable sort
+        // keeps the most recent of any tied blocks first.
+        let mut blocks = self.latest_blocks.iter().rev().collect::<Vec<_>>();
+        blocks.sort_by_key(|block| std::cmp::Reverse(block.num_transactions));
+        blocks.truncate(n);
+        blocks
+    }
+
+    /// [median_block_reward] computes the median of the rewards of the
+    /// retained blocks, where the reward of a block is the sum of all of its
+    /// rewards, and a block without any rewards has a reward of zero.  For an
+    /// even number of blocks, the mean of the two middle rewards is used,
+    /// rounded down.
+    ///
+    /// Returns [None] if there are no retained blocks.
+    pub fn median_block_reward(&self) -> Option<U256> {
+        let mut rewards = self
+            .latest_blocks
+            .iter()
+            .map(|block| {
+                block
+                    .block_reward
+                    .iter()
+                    .fold(U256::zero(), |acc, reward| acc.saturating_add(reward.0))
+            })
+            .collect::<Vec<_>>();
+        if rewards.is_empty() {
+            return None;
+        }
+
+        rewards.sort_unstable();
+        let middle = rewards.len() / 2;
+        if rewards.len() % 2 == 1 {
+            return Some(rewards[middle]);
+        }
+
+        // The halves are summed separately in order to avoid overflowing.
+        let (lower, upper) = (rewards[middle - 1], rewards[middle]);
+        Some(lower / 2 + upper / 2 + (lower % 2 + upper % 2) / 2)
+    }
+
+    /// [blocks_in_last] counts the retained blocks whose time falls within
+    /// the given window leading up to, and including, `now`.  Blocks with a
+    /// time after `now` are not counted.  A window that reaches further back
+    /// than can be represented includes every block up to `now`.
+    pub fn blocks_in_last(&self, window: Duration, now: OffsetDateTime) -> usize {
+        let start = time::Duration::try_from(window)
+            .ok()
+            .and_then(|window| now.checked_sub(window));
+        self.latest_blocks
+            .iter()
+            .filter(|block| {
+                start.map_or(true, |start| block.time.0 >= start) && block.time.0 <= now
+            })
+            .count()
+    }
+
+    /// [block_rate_bounds] returns the minimum and maximum block rate, in
+    /// blocks per minute, across the sliding sub-windows of the given length
+    /// within the span of the retained blocks.  A sub-window starts at the
+    /// time of each retained block, and only the sub-windows that end within
+    /// the retained span are considered.
+    ///
+    /// Returns [None] if the sub-window is empty, or if the retained span is
+    /// shorter than the sub-window.  A sub-window that is too large to be
+    /// represented is always longer than the retained span.
+    pub fn block_rate_bounds(&self, sub_window: Duration) -> Option<(f64, f64)> {
+        if sub_window.is_zero() {
+            return None;
+        }
+        let minutes = sub_window.as_secs_f64() / 60.0;
+        let sub_window = time::Duration::try_from(sub_window).ok()?;
+
+        let times = self
+            .latest_blocks
+            .iter()
+            .map(|block| block.time.0)
+            .collect::<Vec<_>>();
+        let last_time = *times.iter().max()?;
+
+        times
+            .iter()
+            .filter_map(|start| {
+                let end = start.checked_add(sub_window)?;
+                (end <= last_time).then_some((start, end))
+            })
+            .map(|(start, end)| {
+                let blocks = times
+                    .iter()
+                    .filter(|time| *time >= start && **time < end)
+                    .count();
+                blocks as f64 / minutes
+            })
+            .fold(None, |bounds, rate| match bounds {
+                None => Some((rate, rate)),
+                Some((min, max)) => Some((f64::min(min, rate), f64::max(max, rate))),
+            })
+    }
+
+    /// [block_intervals] returns the time between each pair of consecutive
+    /// retained blocks, from oldest to newest.  A block whose time precedes
+    /// that of the block before it results in an interval of zero.
+    pub fn block_intervals(&self) -> Vec<Duration> {
+        self.latest_blocks
+            .iter()
+            .zip(self.latest_blocks.iter().skip(1))
+            .map(|(previous, current)| {
+                Duration::try_from(current.time.0 - previous.time.0).unwrap_or(Duration::ZERO)
+            })
+            .collect()
+    }
+
+    /// [timestamp_anomalies] returns the heights of the consecutive retained
+    /// blocks whose timestamp decreased, as pairs of the height of the block,
+    /// followed by the height of the block that was retained before it.
+    /// Block timestamps should never decrease, so any such pair indicates a
+    /// clock issue upstream.
+    pub fn timestamp_anomalies(&self) -> Vec<(u64, u64)> {
+        self.latest_blocks
+            .iter()
+            .zip(self.latest_blocks.iter().skip(1))
+            .filter(|(previous, current)| current.time.0 < previous.time.0)
+            .map(|(previous, current)| (current.height, previous.height))
+            .collect()
+    }
+
+    /// [estimated_time_to_height] estimates how long after `now` the block at
+    /// the target height will be produced.  The average interval per height
+    /// is measured between the oldest and the newest retained blocks, so that
+    /// any gaps in the retained heights are accounted for, and is projected
+    /// forward from the time of the newest retained block.  If the target
+    /// height is overdue according to the estimate, the estimate is zero.
+    ///
+    /// Returns [None] if the target height has already been reached, if the
+    /// rate of blocks cannot be determined from the retained blocks, or if
+    /// the estimate is too large to be represented as a [Duration].
+    pub fn estimated_time_to_height(&self, target: u64, now: OffsetDateTime) -> Option<Duration> {
+        let oldest = self.oldest_block()?;
+        let latest = self.latest_block()?;
+        if target <= latest.height || latest.height <= oldest.height {
+            return None;
+        }
+
+        let elapsed = (latest.time.0 - oldest.time.0).as_seconds_f64();
+        if elapsed <= 0.0 {
+            return None;
+        }
+
+        let seconds_per_height = elapsed / (latest.height - oldest.height) as f64;
+        let remaining = seconds_per_height * (target - latest.height) as f64
+            - (now - latest.time.0).as_seconds_f64();
+        Duration::try_from_secs_f64(remaining.max(0.0)).ok()
+    }
+
+    /// [block_interval_iqr] returns the inter-quartile range of the
+    /// [block_intervals](Self::block_intervals), which characterizes the
+    /// stability of the block time without being skewed by outliers.  The
+    /// quartiles are linearly interpolated between the nearest intervals.
+    ///
+    /// Returns [None] if fewer than [MIN_IQR_BLOCK_INTERVALS] intervals are
+    /// retained.
+    pub fn block_interval_iqr(&self) -> Option<Duration> {
+        let mut intervals = self
+            .block_intervals()
+            .iter()
+            .map(Duration::as_secs_f64)
+            .collect::<Vec<_>>();
+        if intervals.len() < MIN_IQR_BLOCK_INTERVALS {
+            return None;
+        }
+
+        intervals.sort_by(f64::total_cmp);
+        let iqr = quantile(&intervals, 0.75) - quantile(&intervals, 0.25);
+        Some(Duration::from_secs_f64(iqr.max(0.0)))
+    }
+
+    /// [blocks_with_missing_payload] counts the retained blocks whose payload
+    /// was unavailable when they were processed.
+    pub fn blocks_with_missing_payload(&self) -> usize {
+        self.latest_blocks
+            .iter()
+            .filter(|block| self.missing_payload_heights.contains(&block.height))
+            .count()
+    }
+
+    /// [empty_block_ratio] computes the fraction of the retained blocks that
+    /// did not contain any transactions.  Blocks whose payload was
+    /// unavailable are excluded, as their transactions are unknown.
+    ///
+    /// Returns [None] if there are no such retained blocks.
+    pub fn empty_block_ratio(&self) -> Option<f64> {
+        let (num_empty_blocks, num_blocks) = self
+            .latest_blocks
+            .iter()
+            .filter(|block| !self.missing_payload_heights.contains(&block.height))
+            .fold((0u64, 0u64), |(num_empty_blocks, num_blocks), block| {
+                if block.num_transactions == 0 {
+                    (num_empty_blocks + 1, num_blocks + 1)
+                } else {
+                    (num_empty_blocks, num_blocks + 1)
+                }
+            });
+
+        if num_blocks == 0 {
+            return None;
+        }
+
+        Some(num_empty_blocks as f64 / num_blocks as f64)
+    }
+
+    /// [block_rows] produces a [BlockRow] for every retained block, joining
+    /// the block details with the voters and voter stake that were recorded
+    /// for it.
+    pub fn block_rows(&self) -> Vec<BlockRow> {
+        let participating_stake_by_height = self
+            .latest_voter_stake
+            .iter()
+            .map(|voter_stake| (voter_stake.height, voter_stake.participating_stake))
+            .collect::<HashMap<_, _>>();
+
+        // The blocks and voters are aligned from the most recent entry
+        // backwards.
+        let voters_offset = self.latest_voters.len() as isize - self.latest_blocks.len() as isize;
+
+        self.latest_blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| {
+                let voters_index = index as isize + voters_offset;
+                let voter_count = if voters_index < 0 {
+                    None
+                } else {
+                    self.latest_voters
+                        .get(voters_index as usize)
+                        .map(|voters| voters.count_ones())
+                };
+
+                BlockRow {
+                    height: block.height,
+                    hash: block.hash,
+                    num_transactions: block.num_transactions,
+                    size: block.size,
+                    voter_count,
+                    participating_stake: participating_stake_by_height.get(&block.height).copied(),
+                }
+            })
+            .collect()
+    }
+
+    /// [size_participation_correlation] computes the Pearson correlation
+    /// between the size of each retained block and the fraction of the known
+    /// nodes that voted for it.  Blocks whose voters have not been retained
+    /// are not considered.  A negative correlation indicates that larger
+    /// blocks see lower participation.
+    ///
+    /// Returns [None] if fewer than two blocks can be considered, if there are
+    /// no known nodes, or if either the sizes or the participation have no
+    /// variance.
+    pub fn size_participation_correlation(&self) -> Option<f64> {
+        let node_count = self.node_identity.len();
+        if node_count == 0 {
+            return None;
+        }
+
+        let samples = self
+            .block_rows()
+            .iter()
+            .filter_map(|row| {
+                row.voter_count
+                    .map(|voter_count| (row.size as f64, voter_count as f64 / node_count as f64))
+            })
+            .collect::<Vec<_>>();
+
+        if samples.len() < 2 {
+            return None;
+        }
+
+        let count = samples.len() as f64;
+        let mean_size = samples.iter().map(|(size, _)| size).sum::<f64>() / count;
+        let mean_participation = samples
+            .iter()
+            .map(|(_, participation)| participation)
+            .sum::<f64>()
+            / count;
+
+        let (covariance, size_variance, participation_variance) = samples.iter().fold(
+            (0.0, 0.0, 0.0),
+            |(covariance, size_variance, participation_variance), (size, participation)| {
+                let size_delta = size - mean_size;
+                let participation_delta = participation - mean_participation;
+                (
+                    covariance + size_delta * participation_delta,
+                    size_variance + size_delta * size_delta,
+                    participation_variance + participation_delta * participation_delta,
+                )
+            },
+        );
+
+        if size_variance <= 0.0 || participation_variance <= 0.0 {
+            return None;
+        }
+
+        Some(covariance / (size_variance * participation_variance).sqrt())
+    }
+
+    /// [below_quorum_blocks] returns the heights of the retained blocks whose
+    /// stake weighted participation fell below the given threshold, such as
+    /// `0.67`.  Blocks without a retained [VoterStakeSnapshot], or whose
+    /// total stake is zero, are not considered.
+    pub fn below_quorum_blocks(&self, threshold: f64) -> Vec<u64> {
+        let retained_heights = self
+            .latest_blocks
+            .iter()
+            .map(|block| block.height)
+            .collect::<HashSet<_>>();
+
+        self.latest_voter_stake
+            .iter()
+            .filter(|voter_stake| retained_heights.contains(&voter_stake.height))
+            .filter(|voter_stake| {
+                stake_fraction(voter_stake.participating_stake, voter_stake.total_stake)
+                    .is_some_and(|participation| participation < threshold)
+            })
+            .map(|voter_stake| voter_stake.height)
+            .collect()
+    }
+
+    /// [distinct_fee_recipients] counts the number of unique fee recipients
+    /// across the retained blocks.
+    pub fn distinct_fee_recipients(&self) -> usize {
+        self.latest_blocks
+            .iter()
+            .flat_map(|block| block.fee_recipient.iter())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// [proposer_counts] counts the number of retained blocks that each
+    /// proposer, identified by their [FeeAccount], has proposed.
+    pub fn proposer_counts(&self) -> HashMap<FeeAccount, u64> {
+        let mut proposer_counts = HashMap::new();
+        for block in self.latest_blocks.iter() {
+            for proposer in block.proposer_id.iter() {
+                *proposer_counts.entry(*proposer).or_insert(0) += 1;
+            }
+        }
+
+        proposer_counts
+    }
+
+    /// [proposer_gini] computes the Gini coefficient of the
+    /// [proposer_counts], as a measure of how evenly block production is
+    /// distributed.  A value near 0 indicates an even rotation amongst the
+    /// proposers, while a value near 1 indicates that a single proposer
+    /// dominates.
+    ///
+    /// Only proposers that have proposed at least one retained block are
+    /// considered.
+    ///
+    /// Returns [None] if there are no retained blocks.
+    pub fn proposer_gini(&self) -> Option<f64> {
+        let mut counts = self.proposer_counts().into_values().collect::<Vec<_>>();
+        if counts.is_empty() {
+            return None;
+        }
+
+        counts.sort_unstable();
+
+        let n = counts.len() as f64;
+        let total = counts.iter().sum::<u64>() as f64;
+        let weighted_sum = counts
+            .iter()
+            .enumerate()
+            .map(|(index, count)| (index + 1) as f64 * *count as f64)
+            .sum::<f64>();
+
+        Some((2.0 * weighted_sum) / (n * total) - (n + 1.0) / n)
+    }
+
+    /// [top_proposer_share] returns the fraction of the retained blocks that
+    /// were proposed by the single most frequent proposer.  This is a
+    /// simpler indicator of centralization than the [proposer_gini].
+    ///
+    /// Returns [None] if there are no retained blocks.
+    pub fn top_proposer_share(&self) -> Option<f64> {
+        if self.latest_blocks.is_empty() {
+            return None;
+        }
+
+        let top_count = self.proposer_counts().into_values().max().unwrap_or(0);
+        Some(top_count as f64 / self.latest_blocks.len() as f64)
+    }
+
+    /// [recent_proposers] maps the public key of each proposer within the
+    /// retained window to the height of the most recent block that they
+    /// proposed.
+    ///
+    /// The [BlockDetail] only identifies the proposer by their [FeeAccount],
+    /// so only proposers that have a known fee recipient identity mapping
+    /// are included.
+    pub fn recent_proposers(&self) -> HashMap<BLSPubKey, u64> {
+        let mut recent_proposers = HashMap::new();
+        for block in self.latest_blocks.iter() {
+            for proposer in block.proposer_id.iter() {
+                let Some(public_key) = self.fee_recipient_identity.get(proposer) else {
+                    continue;
+                };
+
+                let latest_height = recent_proposers.entry(*public_key).or_insert(block.height);
+                if *latest_height < block.height {
+                    *latest_height = block.height;
+                }
+            }
+        }
+
+        recent_proposers
+    }
+
+    /// [blocks_proposed_by_now_known] returns the retained blocks that were
+    /// proposed by the node with the given public key, once that node has a
+    /// known [NodeIdentity].  The blocks only record the [FeeAccount] of their
+    /// proposer, so the attribution is resolved when it is requested, see
+    /// [resolve_recipient_identity](Self::resolve_recipient_identity).  This
+    /// means that blocks that were proposed before the node announced its
+    /// identity are attributed to it retroactively.
+    ///
+    /// Returns no blocks if the node does not have a known [NodeIdentity].
+    pub fn blocks_proposed_by_now_known(&self, key: &BLSPubKey) -> Vec<&BlockDetail<SeqTypes>> {
+        self.latest_blocks
+            .iter()
+            .filter(|block| {
+                block.proposer_id.iter().any(|proposer| {
+                    self.resolve_recipient_identity(proposer)
+                        .is_some_and(|node_identity| node_identity.public_key() == key)
+                })
+            })
+            .collect()
+    }
+
+    /// [blocks_with_proposer_location] pairs each of the retained blocks with
+    /// the location of its proposer, if the proposer can be resolved to a
+    /// known [NodeIdentity] that has a location, see
+    /// [resolve_recipient_identity](Self::resolve_recipient_identity).
+    /// Should a block have more than one proposer, the location of the first
+    /// proposer with a known location is used.
+    pub fn blocks_with_proposer_location(
+        &self,
+    ) -> Vec<(&BlockDetail<SeqTypes>, Option<&LocationDetails>)> {
+        self.latest_blocks
+            .iter()
+            .map(|block| {
+                let location = block
+                    .proposer_id
+                    .iter()
+                    .find_map(|proposer| self.resolve_recipient_identity(proposer)?.location());
+                (block, location)
+            })
+            .collect()
+    }
+
+    /// [blocks_with_unknown_proposer] returns the retained blocks for which
+    /// none of the proposers can be resolved to a known [NodeIdentity], see
+    /// [resolve_recipient_identity](Self::resolve_recipient_identity).  These
+    /// are the blocks whose proposers we have no metadata for.
+    pub fn blocks_with_unknown_proposer(&self) -> Vec<&BlockDetail<SeqTypes>> {
+        self.latest_blocks
+            .iter()
+            .filter(|block| {
+                !block
+                    .proposer_id
+                    .iter()
+                    .any(|proposer| self.resolve_recipient_identity(proposer).is_some())
+            })
+            .collect()
+    }
+
+    pub fn stake_table_updated_at(&self) -> Option<OffsetDateTime> {
+        self.stake_table_updated_at
+    }
+
+    pub fn replace_stake_table(
+        &mut self,
+        stake_table: StakeTable<BLSPubKey, StateVerKey, CircuitField>,
+    ) {
+        self.replace_stake_table_at(stake_table, OffsetDateTime::now_utc());
+    }
+
+    /// [replace_stake_table_at] replaces the stake table, recording that it
+    /// was updated at the given time.
+    pub fn replace_stake_table_at(
+        &mut self,
+        stake_table: StakeTable<BLSPubKey, StateVerKey, CircuitField>,
+        updated_at: OffsetDateTime,
+    ) {
+        self.invalidate_network_summary();
+        self.stake_table = stake_table;
+        self.stake_table_updated_at = Some(updated_at);
+
+        // We want to make sure that we're accounting for this node identity
+        // information that we have.  In the case of any new public keys
+        // being added, we want to ensure we have an entry for them in our
+        // node identity list.
+
+        let current_identity_set = self
+            .node_identity
+            .iter()
+            .map(|node_identity| *node_identity.public_key())
+            .collect::<std::collections::HashSet<_>>();
+
+        let stake_table_iter_result = self.stake_table.try_iter(SnapshotVersion::Head);
+        let stake_table_iter = match stake_table_iter_result {
+            Ok(into_iter) => into_iter,
+            Err(_) => return,
+        };
+
+        let missing_node_identity_entries =
+            stake_table_iter.filter(|(key, _, _)| !current_identity_set.contains(key));
+
+        self.node_identity.extend(
+            missing_node_identity_entries.map(|(key, _, _)| NodeIdentity::from_public_key(key)),
+        );
+    }
+
+    pub fn add_latest_block(&mut self, block: BlockDetail<SeqTypes>) {
+        self.add_latest_block_with_payload_availability(block, true);
+    }
+
+    /// [add_latest_block_with_payload_availability] adds the given block,
+    /// recording whether its payload was available.
+    pub fn add_latest_block_with_payload_availability(
+        &mut self,
+        block: BlockDetail<SeqTypes>,
+        payload_available: bool,
+    ) {
+        self.invalidate_network_summary();
+
+        if payload_available {
+            self.missing_payload_heights.remove(&block.height);
+        } else {
+            self.missing_payload_heights.insert(block.height);
+        }
+
+        if self.archived_blocks.capacity() > 0
+            && block.height % self.archive_sampling_stride.max(1) == 0
+        {
+            self.archive_block(&block);
+        }
+
+        if let Some(evicted) = self.latest_blocks.push_back(block) {
+            self.send_evicted_block(evicted);
+        }
+        self.evict_expired_blocks();
+
+        // Heights that are no longer retained do not need to be tracked.
+        // Blocks may be retained out of order, so the front block is not
+        // necessarily the lowest retained height.
+        if let Some(lowest_block_height) = self.latest_blocks.iter().map(|block| block.height).min()
+        {
+            self.missing_payload_heights =
+                self.missing_payload_heights.split_off(&lowest_block_height);
+        }
+    }
+
+    pub fn add_latest_voters(&mut self, voters: BitVec<u16>) {
+        self.invalidate_network_summary();
+        self.record_voters_first_seen(&voters);
+        self.latest_voters.push_back(voters);
+        self.trim_expired_voters();
+    }
+
+    /// [record_voters_first_seen] records the given voters, which are about
+    /// to be retained, as the first voters of every known node that they
+    /// cover, and that has not been seen before.  The voters only cover the
+    /// nodes that were known when they were recorded, so any later nodes are
+    /// first seen in subsequent voters.
+    fn record_voters_first_seen(&mut self, voters: &BitVec<u16>) {
+        for node_identity in self.node_identity.iter().take(voters.len()) {
+            self.voters_first_seen
+                .entry(*node_identity.public_key())
+                .or_insert(self.voters_pushed);
+        }
+        self.voters_pushed += 1;
+    }
+
+    /// [first_seen_index] returns the index, within the retained voters, of
+    /// the first voters that the node with the given public key could have
+    /// voted in.  A node that was first seen before the oldest retained
+    /// voters has an index of zero.
+    ///
+    /// Returns [None] if the node has not been seen within any voters.
+    pub fn first_seen_index(&self, key: &BLSPubKey) -> Option<usize> {
+        let first_seen = *self.voters_first_seen.get(key)?;
+        let oldest_retained = self.voters_pushed - self.latest_voters.len() as u64;
+        Some(first_seen.saturating_sub(oldest_retained) as usize)
+    }
+
+    pub fn add_latest_voter_stake(&mut self, voter_stake: VoterStakeSnapshot) {
+        self.latest_voter_stake.push_back(voter_stake);
+        self.trim_expired_voters();
+    }
+
+    pub fn add_node_identity(&mut self, identity: NodeIdentity) {
+        self.invalidate_network_summary();
+        self.tombstoned_nodes.remove(identity.public_key());
+
+        // We need to check to see if this identity is already in the list,
+        // if it is, we will want to replace it.
+
+        let pub_key = identity.public_key();
+
+        let mut matching_public_keys = self
+            .node_identity
+            .iter()
+            // We want the index of the entry for easier editing
+            .enumerate()
+            .filter(|(_, node_identity)| node_identity.public_key() == pub_key);
+
+        // We only expect this have a single entry.
+        let existing_node_identity_option = matching_public_keys.next();
+
+        debug_assert_eq!(matching_public_keys.next(), None);
+
+        if let Some((index, _)) = existing_node_identity_option {
+            self.node_identity[index] = identity;
+            return;
+        }
+
+        // This entry doesn't appear in our table, so let's add it.
+        self.node_identity.push(identity);
+    }
+
+    /// [reindex_voters] remaps every retained voters [BitVec] from the given
+    /// previous order of the public keys to the current order of the
+    /// [NodeIdentity]s.  This must be called whenever the order of the
+    /// [NodeIdentity]s is changed, as the voters would otherwise be
+    /// attributed to the wrong nodes.  Nodes that are not in the previous
+    /// order are treated as not having voted.
+    pub fn reindex_voters(&mut self, old_order: &[BLSPubKey]) {
+        self.invalidate_network_summary();
+
+        let old_index_by_public_key = old_order
+            .iter()
+            .enumerate()
+            .map(|(index, public_key)| (*public_key, index))
+            .collect::<HashMap<_, _>>();
+
+        let new_to_old_index = self
+            .node_identity
+            .iter()
+            .map(|node_identity| {
+                old_index_by_public_key
+                    .get(node_identity.public_key())
+                    .copied()
+            })
+            .collect::<Vec<_>>();
+
+        for voters in self.latest_voters.iter_mut() {
+            *voters = new_to_old_index
+                .iter()
+                .map(|old_index| {
+                    old_index
+                        .and_then(|old_index| voters.get(old_index).map(|voted| *voted))
+                        .unwrap_or(false)
+                })
+                .collect();
+        }
+    }
+
+    /// [add_node_identities] adds or replaces each of the given
+    /// [NodeIdentity]s in a single pass.  Existing identities are replaced in
+    /// place, and new identities are appended in the order that they are
+    /// given, so that the order of the identities remains aligned with the
+    /// recorded voters.  If the same public key appears more than once, the
+    /// last occurrence wins.
+    ///
+    /// Returns the number of identities that were added, followed by the
+    /// number of existing identities that were updated.
+    pub fn add_node_identities(&mut self, identities: Vec<NodeIdentity>) -> (usize, usize) {
+        self.invalidate_network_summary();
+
+        let initial_len = self.node_identity.len();
+        let mut index_by_public_key = self
+            .node_identity
+            .iter()
+            .enumerate()
+            .map(|(index, node_identity)| (*node_identity.public_key(), index))
+            .collect::<HashMap<_, _>>();
+        let mut updated = HashSet::new();
+
+        for identity in identities {
+            self.tombstoned_nodes.remove(identity.public_key());
+            match index_by_public_key.get(identity.public_key()) {
+                Some(&index) => {
+                    if index < initial_len {
+                        updated.insert(index);
+                    }
+                    self.node_identity[index] = identity;
+                }
+                None => {
+                    index_by_public_key.insert(*identity.public_key(), self.node_identity.len());
+                    self.node_identity.push(identity);
+                }
+            }
+        }
+
+        (self.node_identity.len() - initial_len, updated.len())
+    }
+}
+
+/// [participation_rate] computes the fraction of the given voting pattern in
+/// which the node voted.
+///
+/// Returns [None] if the voting pattern is empty.
+fn participation_rate(voting_pattern: &[bool]) -> Option<f64> {
+    if voting_pattern.is_empty() {
+        return None;
+    }
+
+    let votes = voting_pattern.iter().filter(|voted| **voted).count();
+    Some(votes as f64 / voting_pattern.len() as f64)
+}
+
+/// [quantile] returns the given quantile of the given sorted, non-empty,
+/// values, linearly interpolating between the nearest values.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+/// [u256_to_f64] converts the given [U256] into the nearest [f64].
+fn u256_to_f64(value: U256) -> f64 {
+    value
+        .0
+        .iter()
+        .rev()
+        .fold(0.0, |acc, limb| acc * 2f64.powi(64) + *limb as f64)
+}
+
+/// [stake_fraction] computes the fraction of the total stake that the given
+/// stake represents, with a precision of one millionth.
+///
+/// Returns [None] if the total stake is zero.
+fn stake_fraction(stake: U256, total_stake: U256) -> Option<f64> {
+    const PRECISION: u64 = 1_000_000;
+    if total_stake.is_zero() {
+        return None;
+    }
+
+    let scaled = stake.saturating_mul(U256::from(PRECISION)) / total_stake;
+    Some(scaled.low_u64() as f64 / PRECISION as f64)
+}
+
+/// [recover_poisoned] recovers the guard of the given lock after a panic
+/// poisoned it while the lock was held.  The data that is guarded by the
+/// locks within the [DataState] is only ever a cache, or is replaced as a
+/// whole, so it remains usable, and propagating the panic to every
+/// subsequent reader would only take down the service.
+fn recover_poisoned<T>(lock: &str, err: PoisonError<T>) -> T {
+    tracing::warn!("the {} was poisoned by a panic, recovering", lock);
+    err.into_inner()
+}
+
+/// [retained_capacity] guards against a capacity of zero for the buffers
+/// that every statistic depends on.  Such a buffer would silently discard
+/// everything that is added to it, so a capacity of zero is clamped to one,
+/// and a warning is emitted.
+fn retained_capacity(buffer: &str, capacity: usize) -> usize {
+    if capacity == 0 {
+        tracing::warn!(
+            "a capacity of zero for the {} would discard everything, using a capacity of one instead",
+            buffer
+        );
+        return 1;
+    }
+
+    capacity
+}
+
+/// [canonical_bytes] returns the compressed canonical serialization of the
+/// given value.
+fn canonical_bytes<T: CanonicalSerialize>(value: &T) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    value
+        .serialize_compressed(&mut bytes)
+        .expect("serializing into a Vec should not fail");
+    bytes
+}
+
+/// [encode_hex] encodes the canonical serialization of the given value as a
+/// `0x` prefixed hex string.
+fn encode_hex<T: CanonicalSerialize>(value: &T) -> String {
+    canonical_bytes(value)
+        .iter()
+        .fold(String::from("0x"), |acc, byte| {
+            format!("{}{:02x}", acc, byte)
+        })
+}
+
+/// [render_timestamp] formats the given [Timestamp] as an RFC 3339 string in
+/// the given offset from UTC, in seconds.  If the offset is out of range, the
+/// timestamp is rendered in UTC instead.
+pub fn render_timestamp(timestamp: &Timestamp, utc_offset_seconds: i32) -> String {
+    let offset = UtcOffset::from_whole_seconds(utc_offset_seconds).unwrap_or_else(|err| {
+        tracing::warn!(
+            "render timestamp: invalid utc offset {}, rendering in utc: {}",
+            utc_offset_seconds,
+            err
+        );
+        UtcOffset::UTC
+    });
+
+    timestamp
+        .0
+        .to_offset(offset)
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| timestamp.0.to_string())
+}
+
+/// [create_block_detail_from_leaf] is a helper function that will build a
+/// [BlockDetail] from the reference to [Leaf].
+pub fn create_block_detail_from_leaf(leaf: &Leaf<SeqTypes>) -> BlockDetail<SeqTypes> {
+    create_block_detail_from_leaf_with_filter(leaf, |_| true)
+}
+
+/// [create_block_detail_from_leaf_with_filter] builds a [BlockDetail] from
+/// the reference to [Leaf], in the same way as
+/// [create_block_detail_from_leaf], except that only the transactions for
+/// which `include` returns true count toward the number of transactions and
+/// the size of the block.  This allows, for example, the size of a block to
+/// be accounted for a single namespace.
+pub fn create_block_detail_from_leaf_with_filter<F>(
+    leaf: &Leaf<SeqTypes>,
+    include: F,
+) -> BlockDetail<SeqTypes>
+where
+    F: Fn(&Transaction) -> bool,
+{
+    let block_header = leaf.block_header();
+    let block_payload = &leaf.block_payload().unwrap_or(Payload::empty().0);
+
+    let (num_transactions, total_payload_size) =
+        count_transactions(block_payload, block_header.metadata(), include);
+
+    BlockDetail::<SeqTypes> {
+        hash: block_header.commitment(),
+        height: block_header.height(),
+        time: Timestamp(
+            OffsetDateTime::from_unix_timestamp(block_header.timestamp() as i64)
+                .unwrap_or(OffsetDateTime::UNIX_EPOCH),
+        ),
+        proposer_id: block_header.proposer_id(),
+        num_transactions,
+        block_reward: vec![block_header.fee_info_balance().into()],
+        fee_recipient: block_header.fee_info_account(),
+        size: total_payload_size,
+    }
+}
+
+/// [count_transactions] calculates the number of transactions and the total
+/// payload size of the transactions contained within the [Payload] for which
+/// `include` returns true.  A transaction that cannot be retrieved from the
+/// [Payload] is counted, but contributes nothing to the size.
+fn count_transactions<F>(block_payload: &Payload, metadata: &NsTable, include: F) -> (u64, u64)
+where
+    F: Fn(&Transaction) -> bool,
+{
+    block_payload.iter(metadata).fold(
+        (0u64, 0u64),
+        |(num_transactions, total_payload_size), tx_index| match block_payload
+            .transaction(&tx_index)
+        {
+            Some(tx) if include(&tx) => (
+                num_transactions + 1,
+                total_payload_size + tx.payload().len() as u64,
+            ),
+            Some(_) => (num_transactions, total_payload_size),
+            None => (num_transactions + 1, total_payload_size),
+        },
+    )
+}
+
+/// [ProcessLeafError] represents the error that can occur when processing
+/// a [Leaf].
+#[derive(Debug)]
+pub enum ProcessLeafError {
+    BlockSendError(SendError),
+    VotersSendError(SendError),
+    VoterStakeSendError(SendError),
+}
+
+impl std::fmt::Display for ProcessLeafError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessLeafError::BlockSendError(err) => {
+                write!(f, "error sending block detail to sender: {}", err)
+            }
+            ProcessLeafError::VotersSendError(err) => {
+                write!(f, "error sending voters to sender: {}", err)
+            }
+            ProcessLeafError::VoterStakeSendError(err) => {
+                write!(f, "error sending voter stake snapshot to sender: {}", err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProcessLeafError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProcessLeafError::BlockSendError(err) => Some(err),
+            ProcessLeafError::VotersSendError(err) => Some(err),
+            ProcessLeafError::VoterStakeSendError(err) => Some(err),
+        }
+    }
+}
+
+/// WAIT_FOR_HEIGHT_POLL_INTERVAL is the interval at which [wait_for_height]
+/// checks the [DataState] for the desired height.
+const WAIT_FOR_HEIGHT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// [WaitError] represents the error that can occur when waiting for the
+/// [DataState] to reach a specific height.
+#[derive(Debug, PartialEq, Eq)]
+pub enum WaitError {
+    Timeout { height: u64 },
+}
+
+impl std::fmt::Display for WaitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaitError::Timeout { height } => {
+                write!(f, "timed out waiting for height {}", height)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WaitError {}
+
+/// [wait_for_height] waits until a block with at least the given height has
+/// been processed into the [DataState], or until the given timeout has
+/// elapsed.  This is intended to allow for synchronizing with the processing
+/// of blocks without relying on arbitrary sleeps.
+pub async fn wait_for_height(
+    data_state: Arc<RwLock<DataState>>,
+    height: u64,
+    timeout: Duration,
+) -> Result<(), WaitError> {
+    let wait = async {
+        loop {
+            let latest_block_height = data_state.read().await.latest_block_height();
+            if latest_block_height.is_some_and(|latest_height| latest_height >= height) {
+                return;
+            }
+
+            async_std::task::sleep(WAIT_FOR_HEIGHT_POLL_INTERVAL).await;
+        }
+    };
+
+    async_std::future::timeout(timeout, wait)
+        .await
+        .map_err(|_| WaitError::Timeout { height })
+}
+
+/// [summary_stream] produces the [NetworkSummary] of the [DataState] once
+/// every interval, regardless of the rate at which blocks are processed.  The
+/// first summary is produced after the first interval has elapsed.
+pub fn summary_stream(
+    data_state: Arc<RwLock<DataState>>,
+    interval: Duration,
+) -> impl Stream<Item = NetworkSummary> {
+    futures::stream::unfold(data_state, move |data_state| async move {
+        async_std::task::sleep(interval).await;
+        let network_summary = data_state.read().await.network_summary();
+        Some((network_summary, data_state))
+    })
+}
+
+/// [compute_voters_bitvec] remaps the voters of a Quorum Certificate from the
+/// order of the Stake Table into the order of the given [NodeIdentity]s.
+///
+/// Before any [NodeIdentity]s are known, as is the case on a fresh start,
+/// the remapping would always produce an empty [BitVec], which would appear
+/// as though nobody voted.  To avoid this loss of information, the voters are
+/// returned in Stake Table order instead, and a warning is emitted to
+/// indicate that voter attribution is unavailable until identities are
+/// known.
+fn compute_voters_bitvec(
+    node_identity: &[NodeIdentity],
+    voters_set: &HashSet<BLSPubKey>,
+    stake_table_voters_bit_vec: BitVec<u16>,
+) -> BitVec<u16> {
+    if node_identity.is_empty() {
+        if stake_table_voters_bit_vec.any() {
+            tracing::warn!(
+                "no node identities are known, voter attribution is unavailable, \
+                 recording voters in stake table order"
+            );
+        }
+
+        return stake_table_voters_bit_vec;
+    }
+
+    let voters_bitvec = node_identity.iter().fold(
+        BitVec::with_capacity(node_identity.len()),
+        |mut acc, node_identity| {
+            acc.push(voters_set.contains(node_identity.public_key()));
+            acc
+        },
+    );
+
+    // Every reader of the voters pairs each bit with the [NodeIdentity] at
+    // the same index, so the length must match exactly.  The capacity is
+    // irrelevant.
+    debug_assert_eq!(voters_bitvec.len(), node_identity.len());
+
+    voters_bitvec
+}
+
+/// [process_incoming_leaf] is a helper function that will process an incoming
+/// [Leaf] and update the [DataState] with the new information.
+/// Additionally, the block that is contained within the [Leaf] will be
+/// computed into a [BlockDetail] and sent to the [Sink] so that it can be
+/// processed for real-time considerations.
+/// If a voter stake [Sink] is provided, the stake weighted participation of
+/// the voters will be sent to it as a [VoterStakeSnapshot].
+/// The voters are sent to each of the given voters [Sink]s.  A voters [Sink]
+/// that fails is removed, so that it does not affect the others, unless it
+/// is the only one that remains.
+/// The finalization lag of the block is measured against `received_at`, the
+/// time at which the [Leaf] was received from the leaf stream.
+async fn process_incoming_leaf<BDSink, BVSink, VSSink>(
+    leaf: Leaf<SeqTypes>,
+    received_at: OffsetDateTime,
+    data_state: Arc<RwLock<DataState>>,
+    mut block_sender: BDSink,
+    voters_senders: &mut Vec<BVSink>,
+    voter_stake_sender: Option<VSSink>,
+) -> Result<(), ProcessLeafError>
+where
+    Header: BlockHeader<SeqTypes> + QueryableHeader<SeqTypes> + ExplorerHeader<SeqTypes>,
+    Payload: BlockPayload<SeqTypes>,
+    BDSink: Sink<BlockDetail<SeqTypes>, Error = SendError> + Unpin,
+    BVSink: Sink<BitVec<u16>, Error = SendError> + Unpin,
+    VSSink: Sink<VoterStakeSnapshot, Error = SendError> + Unpin,
+{
+    let transaction_filter = data_state.read().await.transaction_filter.clone();
+    let include = |tx: &Transaction| {
+        transaction_filter
+            .as_ref()
+            .map_or(true, |transaction_filter| transaction_filter(tx))
+    };
+    let block_detail = create_block_detail_from_leaf_with_filter(&leaf, include);
+    let payload_available = leaf.block_payload().is_some();
+    let block_detail_copy = create_block_detail_from_leaf_with_filter(&leaf, include);
+
+    // This BitVec should be in the same order as the Stake Table.
+    // The StakeTable will be able to change its order between epochs,
+    // which means that its order can change between blocks.
+    // However, the BitVec is a really nice size in order for storing
+    // information.  We should be able to remap the BitVec order from
+    // the StakeTable order to our installed order representation.  This
+    // should allow us to still store as a BitVec while containing our
+    // out order of the voters.
+    // We will need to recompute these BitVecs if the node information that
+    // is stored shrinks instead of growing.
+
+    let mut data_state_write_lock_guard = data_state.write().await;
+    let stake_table_voters_bit_vec = data_state_write_lock_guard
+        .voter_attribution_source
+        .voters(&leaf);
+
+    // Leaves that are known to be bad are skipped without being recorded.
+    if data_state_write_lock_guard.is_blocklisted(&block_detail) {
+        tracing::warn!(
+            "process incoming leaf: skipping blocklisted leaf at height {}: {}",
+            block_detail.height,
+            block_detail.hash
+        );
+        return Ok(());
+    }
+
+    // The upstream may replay the leaf that we have most recently processed,
+    // in which case we do not want to record it a second time.
+    if let Some(latest_block) = data_state_write_lock_guard.latest_blocks.back() {
+        if latest_block.height == block_detail.height && latest_block.hash == block_detail.hash {
+            data_state_write_lock_guard.duplicate_leaves += 1;
+            tracing::debug!(
+                "process incoming leaf: skipping duplicate leaf at height {}",
+                block_detail.height
+            );
+            return Ok(());
+        }
+    }
+
+    // The empty payload fallback was taken when building the block detail.
+    // Blocklisted and duplicate leaves are skipped before this point, so
+    // that they are not counted.
+    if !payload_available {
+        data_state_write_lock_guard.payload_fallback_count += 1;
+    }
+
+    // If we have already stored a different block for this height, then we
+    // have observed a fork.  We record it, but keep the block that we
+    // observed first.
+    if let Some(fork_event) = data_state_write_lock_guard.detect_fork(&block_detail) {
+        tracing::warn!(
+            "process incoming leaf: fork detected at height {}: {} != {}",
+            fork_event.height,
+            fork_event.hash_a,
+            fork_event.hash_b
+        );
+        data_state_write_lock_guard.send_anomaly_event(AnomalyEvent::Fork(fork_event.clone()));
+        data_state_write_lock_guard
+            .fork_events
+            .push_back(fork_event);
+        return Ok(());
+    }
+
+    data_state_write_lock_guard.check_leaf_anomalies(
+        block_detail.height,
+        &stake_table_voters_bit_vec,
+        payload_available,
+    );
+
+    // Voter tracking can be disabled for deployments that only care about
+    // the blocks themselves, as attributing the voters is comparatively
+    // expensive.
+    let voters = if data_state_write_lock_guard.voter_tracking_enabled {
+        Some(
+            data_state_write_lock_guard
+                .compute_voters(block_detail.height, stake_table_voters_bit_vec),
+        )
+    } else {
+        None
+    };
+
+    data_state_write_lock_guard.record_finalization_lag(&block_detail, received_at);
+    data_state_write_lock_guard.update_cumulative_counters(&block_detail);
+    data_state_write_lock_guard.running_aggregates.update(
+        &block_detail,
+        voters.as_ref().and_then(|(_, voter_stake_snapshot)| {
+            stake_fraction(
+                voter_stake_snapshot.participating_stake,
+                voter_stake_snapshot.total_stake,
+            )
+        }),
+    );
+    data_state_write_lock_guard
+        .add_latest_block_with_payload_availability(block_detail, payload_available);
+    if let Some((voters_bitvec, voter_stake_snapshot)) = &voters {
+        data_state_write_lock_guard.record_voters_first_seen(voters_bitvec);
+        data_state_write_lock_guard
+            .latest_voters
+            .push_back(voters_bitvec.clone());
+        data_state_write_lock_guard
+            .latest_voter_stake
+            .push_back(voter_stake_snapshot.clone());
+        data_state_write_lock_guard.trim_expired_voters();
+    }
+    data_state_write_lock_guard.recent_leaves.push_back(leaf);
+
+    drop(data_state_write_lock_guard);
+
+    if let Err(err) = block_sender.send(block_detail_copy).await {
+        // We have an error that prevents us from continuing
+        return Err(ProcessLeafError::BlockSendError(err));
+    }
+
+    let Some((voters_bitvec, voter_stake_snapshot)) = voters else {
+        return Ok(());
+    };
+
+    let mut index = 0;
+    while index < voters_senders.len() {
+        let Err(err) = voters_senders[index].send(voters_bitvec.clone()).await else {
+            index += 1;
+            continue;
+        };
+
+        if voters_senders.len() == 1 {
+            // We have an error that prevents us from continuing
+            return Err(ProcessLeafError::VotersSendError(err));
+        }
+
+        tracing::warn!(
+            "process incoming leaf: dropping voters sender {} after failing to send: {}",
+            index,
+            err
+        );
+        voters_senders.remove(index);
+    }
+
+    if let Some(mut voter_stake_sender) = voter_stake_sender {
+        if let Err(err) = voter_stake_sender.send(voter_stake_snapshot).await {
+            // We have an error that prevents us from continuing
+            return Err(ProcessLeafError::VoterStakeSendError(err));
+        }
+    }
+
+    Ok(())
+}
+
+/// PAUSED_LEAF_STREAM_POLL_INTERVAL is the interval at which a paused
+/// [ProcessLeafStreamTask] checks whether it has been resumed.
+const PAUSED_LEAF_STREAM_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// [ProcessLeafStreamTask] represents the task that is responsible for
+/// processing a stream of incoming [Leaf]s.
+///
+/// The processing can be paused for maintenance without tearing down the
+/// task.  While paused, at most a single [Leaf] is held by the task, and the
+/// remaining [Leaf]s are retained by the upstream instead.  For a bounded
+/// channel this means that the upstream is subject to back pressure once the
+/// channel is full.  Nothing is dropped, and the retained [Leaf]s are
+/// processed in order once the processing is resumed.
+pub struct ProcessLeafStreamTask {
+    pub task_handle: Option<JoinHandle<()>>,
+    paused: Arc<AtomicBool>,
+}
+
+impl ProcessLeafStreamTask {
+    /// [new] creates a new [ProcessLeafStreamTask] that will process a stream
+    /// of incoming [Leaf]s.
+    ///
+    /// Calling this function will create an asynchronous task that will start
+    /// processing immediately. The handle for the task will be stored within
+    /// the returned structure.
+    pub fn new<S, K1, K2>(
+        leaf_receiver: S,
+        data_state: Arc<RwLock<DataState>>,
+        block_detail_sender: K1,
+        voters_sender: K2,
+    ) -> Self
+    where
+        S: Stream<Item = Leaf<SeqTypes>> + Send + Sync + Unpin + 'static,
+        K1: Sink<BlockDetail<SeqTypes>, Error = SendError> + Clone + Send + Sync + Unpin + 'static,
+        K2: Sink<BitVec<u16>, Error = SendError> + Clone + Send + Sync + Unpin + 'static,
+    {
+        let paused = Arc::new(AtomicBool::new(false));
+        let task_handle = async_std::task::spawn(Self::process_leaf_stream(
+            leaf_receiver,
+            data_state.clone(),
+            block_detail_sender,
+            vec![voters_sender],
+            None::<Sender<VoterStakeSnapshot>>,
+            paused.clone(),
+        ));
+
+        Self {
+            task_handle: Some(task_handle),
+            paused,
+        }
+    }
+
+    /// [new_with_voters_senders] creates a new [ProcessLeafStreamTask] that
+    /// behaves the same as one created with [new], except that the voters
+    /// are sent to each of the given voters senders.  A voters sender that
+    /// fails is dropped with a warning, without affecting the others.
+    pub fn new_with_voters_senders<S, K1, K2>(
+        leaf_receiver: S,
+        data_state: Arc<RwLock<DataState>>,
+        block_detail_sender: K1,
+        voters_senders: Vec<K2>,
+    ) -> Self
+    where
+        S: Stream<Item = Leaf<SeqTypes>> + Send + Sync + Unpin + 'static,
+        K1: Sink<BlockDetail<SeqTypes>, Error = SendError> + Clone + Send + Sync + Unpin + 'static,
+        K2: Sink<BitVec<u16>, Error = SendError> + Send + Sync + Unpin + 'static,
+    {
+        let paused = Arc::new(AtomicBool::new(false));
+        let task_handle = async_std::task::spawn(Self::process_leaf_stream(
+            leaf_receiver,
+            data_state.clone(),
+            block_detail_sender,
+            voters_senders,
+            None::<Sender<VoterStakeSnapshot>>,
+            paused.clone(),
+        ));
+
+        Self {
+            task_handle: Some(task_handle),
+            paused,
+        }
+    }
+
+    /// [new_with_voter_stake_sender] creates a new [ProcessLeafStreamTask]
+    /// that behaves the same as one created with [new], with the addition
+    /// that a [VoterStakeSnapshot] will be sent to the given voter stake
+    /// sender for every processed [Leaf].
+    pub fn new_with_voter_stake_sender<S, K1, K2, K3>(
+        leaf_receiver: S,
+        data_state: Arc<RwLock<DataState>>,
+        block_detail_sender: K1,
+        voters_sender: K2,
+        voter_stake_sender: K3,
+    ) -> Self
+    where
+        S: Stream<Item = Leaf<SeqTypes>> + Send + Sync + Unpin + 'static,
+        K1: Sink<BlockDetail<SeqTypes>, Error = SendError> + Clone + Send + Sync + Unpin + 'static,
+        K2: Sink<BitVec<u16>, Error = SendError> + Clone + Send + Sync + Unpin + 'static,
+        K3: Sink<VoterStakeSnapshot, Error = SendError> + Clone + Send + Sync + Unpin + 'static,
+    {
+        let paused = Arc::new(AtomicBool::new(false));
+        let task_handle = async_std::task::spawn(Self::process_leaf_stream(
+            leaf_receiver,
+            data_state.clone(),
+            block_detail_sender,
+            vec![voters_sender],
+            Some(voter_stake_sender),
+            paused.clone(),
+        ));
+
+        Self {
+            task_handle: Some(task_handle),
+            paused,
+        }
+    }
+
+    /// [pause] stops the processing of [Leaf]s until [resume] is called.
+    /// A [Leaf] that is already being processed is completed first.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// [resume] continues the consumption of [Leaf]s after [pause].
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// [process_leaf_stream] allows for the consumption of a [Stream] when
+    /// attempting to process new incoming [Leaf]s.  No [Leaf]s are processed
+    /// while `paused` is set.
+    async fn process_leaf_stream<S, BDSink, BVSink, VSSink>(
+        stream: S,
+        data_state: Arc<RwLock<DataState>>,
+        block_sender: BDSink,
+        mut voters_senders: Vec<BVSink>,
+        voter_stake_sender: Option<VSSink>,
+        paused: Arc<AtomicBool>,
+    ) where
+        S: Stream<Item = Leaf<SeqTypes>> + Unpin,
+        Header: BlockHeader<SeqTypes> + QueryableHeader<SeqTypes> + ExplorerHeader<SeqTypes>,
+        Payload: BlockPayload<SeqTypes>,
+        BDSink: Sink<BlockDetail<SeqTypes>, Error = SendError> + Clone + Unpin,
+        BVSink: Sink<BitVec<u16>, Error = SendError> + Unpin,
+        VSSink: Sink<VoterStakeSnapshot, Error = SendError> + Clone + Unpin,
+    {
+        let mut stream = annotate_leaf_stream(stream);
+        loop {
+            let leaf_result = stream.next().await;
+            let AnnotatedLeaf {
+                leaf,
+                sequence: _,
+                ingested_at,
+                received_at,
+            } = if let Some(annotated_leaf) = leaf_result {
+                annotated_leaf
+            } else {
+                // We have reached the end of the stream.  This is a clean
+                // termination, so it is not reported as an error.
+                tracing::info!("process leaf stream: end of stream reached for leaf stream.");
+                return;
+            };
+
+            // A leaf that is received while paused is held until the
+            // processing is resumed.
+            while paused.load(Ordering::SeqCst) {
+                async_std::task::sleep(PAUSED_LEAF_STREAM_POLL_INTERVAL).await;
+            }
+
+            data_state.write().await.record_leaf_annotation(
+                leaf.height(),
+                ingested_at,
+                Instant::now(),
+            );
+
+            let leaf_height = leaf.height();
+            if let Err(err) = process_incoming_leaf(
+                leaf,
+                received_at,
+                data_state.clone(),
+                block_sender.clone(),
+                &mut voters_senders,
+                voter_stake_sender.clone(),
+            )
+            .await
+            {
+                // We have an error that prevents us from continuing
+                tracing::error!(
+                    "process leaf stream: error processing leaf at height {}: {}",
+                    leaf_height,
+                    err
+                );
+
+                // At the moment, all underlying errors are due to `SendError`
+                // which will ultimately mean that further processing attempts
+                // will fail, and be fruitless.
+                match err {
+                    ProcessLeafError::BlockSendError(_) => {
+                        panic!("ProcessLeafStreamTask: process_incoming_leaf failed, underlying sink is closed, blocks will stagnate: {}", err)
+                    }
+                    ProcessLeafError::VotersSendError(_) => {
+                        panic!("ProcessLeafStreamTask: process_incoming_leaf failed, underlying sink is closed, voters will stagnate: {}", err)
+                    }
+                    ProcessLeafError::VoterStakeSendError(_) => {
+                        panic!("ProcessLeafStreamTask: process_incoming_leaf failed, underlying sink is closed, voter stake snapshots will stagnate: {}", err)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// [Drop] implementation for [ProcessLeafStreamTask] that will cancel the
+/// task if it is dropped.
+impl Drop for ProcessLeafStreamTask {
+    fn drop(&mut self) {
+        let task_handle = self.task_handle.take();
+        if let Some(task_handle) = task_handle {
+            async_std::task::block_on(task_handle.cancel());
+        }
+    }
+}
+
+/// [ProcessNodeIdentityError] represents the error that can occur when processing
+/// a [NodeIdentity].
+#[derive(Debug)]
+pub enum ProcessNodeIdentityError {
+    SendError(SendError),
+}
+
+impl std::fmt::Display for ProcessNodeIdentityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessNodeIdentityError::SendError(err) => {
+                write!(f, "error sending node identity to sender: {}", err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProcessNodeIdentityError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProcessNodeIdentityError::SendError(err) => Some(err),
+        }
+    }
+}
+
+impl From<SendError> for ProcessNodeIdentityError {
+    fn from(err: SendError) -> Self {
+        ProcessNodeIdentityError::SendError(err)
+    }
+}
+
+/// [process_incoming_node_identity] is a helper function that will process an
+/// incoming [NodeIdentity] and update the [DataState] with the new information.
+/// A missing location is resolved with the given [GeoResolver] beforehand.
+/// Additionally, the [NodeIdentity] will be sent to the [Sink] so that it can
+/// be processed for real-time considerations.
+async fn process_incoming_node_identity<NISink>(
+    mut node_identity: NodeIdentity,
+    data_state: Arc<RwLock<DataState>>,
+    geo_resolver: &dyn GeoResolver,
+    mut node_identity_sender: NISink,
+) -> Result<(), ProcessNodeIdentityError>
+where
+    NISink: Sink<NodeIdentity, Error = SendError> + Unpin,
+{
+    // The resolution may be slow, so it is performed before the lock on the
+    // DataState is acquired.
+    resolve_missing_location(geo_resolver, &mut node_identity).await;
+
+    let mut data_state_write_lock_guard = data_state.write().await;
+    data_state_write_lock_guard.add_node_identity(node_identity.clone());
+    node_identity_sender.send(node_identity).await?;
+
+    Ok(())
+}
+
+/// [ProcessNodeIdentityStreamTask] represents the task that is responsible for
+/// processing a stream of incoming [NodeIdentity]s and updating the [DataState]
+/// with the new information.
+pub struct ProcessNodeIdentityStreamTask {
+    pub task_handle: Option<JoinHandle<()>>,
+}
+
+impl ProcessNodeIdentityStreamTask {
+    /// [new] creates a new [ProcessNodeIdentityStreamTask] that will process a
+    /// stream of incoming [NodeIdentity]s.
+    ///
+    /// Calling this function will create an asynchronous task that will start
+    /// processing immediately. The handle for the task will be stored within
+    /// the returned structure.
+    pub fn new<S, K>(
+        node_identity_receiver: S,
+        data_state: Arc<RwLock<DataState>>,
+        node_identity_sender: K,
+    ) -> Self
+    where
+        S: Stream<Item = NodeIdentity> + Send + Sync + Unpin + 'static,
+        K: Sink<NodeIdentity, Error = SendError> + Clone + Send + Sync + Unpin + 'static,
+    {
+        Self::new_with_geo_resolver(
+            node_identity_receiver,
+            data_state,
+            node_identity_sender,
+            Arc::new(NoOpGeoResolver),
+        )
+    }
+
+    /// [new_with_geo_resolver] creates a new [ProcessNodeIdentityStreamTask]
+    /// that behaves the same as one created with [new], with the addition
+    /// that the location of any [NodeIdentity] that does not announce one
+    /// will be resolved with the given [GeoResolver].
+    pub fn new_with_geo_resolver<S, K>(
+        node_identity_receiver: S,
+        data_state: Arc<RwLock<DataState>>,
+        node_identity_sender: K,
+        geo_resolver: Arc<dyn GeoResolver>,
+    ) -> Self
+    where
+        S: Stream<Item = NodeIdentity> + Send + Sync + Unpin + 'static,
+        K: Sink<NodeIdentity, Error = SendError> + Clone + Send + Sync + Unpin + 'static,
+    {
+        let task_handle = async_std::task::spawn(Self::process_node_identity_stream(
+            node_identity_receiver,
+            data_state.clone(),
+            node_identity_sender,
+            geo_resolver,
+        ));
+
+        Self {
+            task_handle: Some(task_handle),
+        }
+    }
+
+    /// [process_node_identity_stream] allows for the consumption of a [Stream] when
+    /// attempting to process new incoming [NodeIdentity]s.
+    /// This function will process the incoming [NodeIdentity] and update the
+    /// [DataState] with the new information.
+    /// Additionally, the [NodeIdentity] will be sent to the [Sink] so that it can
+    /// be processed for real-time considerations.
+    async fn process_node_identity_stream<S, NISink>(
+        mut stream: S,
+        data_state: Arc<RwLock<DataState>>,
+        node_identity_sender: NISink,
+        geo_resolver: Arc<dyn GeoResolver>,
+    ) where
+        S: Stream<Item = NodeIdentity> + Unpin,
+        NISink: Sink<NodeIdentity, Error = SendError> + Clone + Unpin,
+    {
+        loop {
+            let node_identity_result = stream.next().await;
+            let node_identity = if let Some(node_identity) = node_identity_result {
+                node_identity
+            } else {
+                // We have reached the end of the stream
+                tracing::info!(
+                    "process node identity stream: end of stream reached for node identity stream."
+                );
+                return;
+            };
+
+            if let Err(err) = process_incoming_node_identity(
+                node_identity,
+                data_state.clone(),
+                geo_resolver.as_ref(),
+                node_identity_sender.clone(),
+            )
+            .await
+            {
+                // We have an error that prevents us from continuing
+                tracing::error!(
+                    "process node identity stream: error processing node identity: {}",
+                    err
+                );
+
+                // The only underlying class of errors that can be returned from
+                // `process_incoming_node_identity` are due to `SendError` which
+                // will ultimately mean that further processing attempts will fail
+                // and be fruitless.
+                panic!("ProcessNodeIdentityStreamTask: process_incoming_node_identity failed, underlying sink is closed, node identities will stagnate: {}", err);
+            }
+        }
+    }
+}
+
+/// [Drop] implementation for [ProcessNodeIdentityStreamTask] that will cancel
+/// the task if it is dropped.
+impl Drop for ProcessNodeIdentityStreamTask {
+    fn drop(&mut self) {
+        let task_handle = self.task_handle.take();
+        if let Some(task_handle) = task_handle {
+            async_std::task::block_on(task_handle.cancel());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        count_transactions, create_block_detail_from_leaf, AnomalyEvent, DataState, ForkEvent,
+        ProcessLeafStreamTask, Trend, VoterAttributionSource, VoterStakeSnapshot, WaitError,
+    };
+    use crate::service::data_state::{
+        geo_resolver::tests::MockGeoResolver, BlockOrGap, BoundedBuffer, HeaderSummary,
+        LocationDetails, NodeIdentity, ProcessNodeIdentityStreamTask, ToHeaderSummary,
+    };
+    use async_std::{prelude::FutureExt, sync::RwLock};
+    use bitvec::vec::BitVec;
+    use espresso_types::{
+        v0_3::ChainConfig, BlockMerkleTree, FeeAccount, FeeAmount, FeeMerkleTree, Leaf,
+        NamespaceId, NodeState, Payload, SeqTypes, Transaction, ValidatedState,
+    };
+    use ethers::types::U256;
+    use futures::{channel::mpsc, SinkExt, StreamExt};
+    use hotshot_query_service::explorer::{BlockDetail, Timestamp};
+    use hotshot_stake_table::vec_based::StakeTable;
+    use hotshot_types::{
+        light_client::{CircuitField, StateKeyPair, StateVerKey},
+        signature_key::BLSPubKey,
+        traits::{
+            signature_key::SignatureKey,
+            stake_table::{SnapshotVersion, StakeTableScheme},
+            BlockPayload,
+        },
+    };
+    use std::{sync::Arc, time::Duration};
+    use time::OffsetDateTime;
+    use url::Url;
+
+    /// [create_test_block_detail] creates a [BlockDetail] that is derived from
+    /// the genesis [Leaf], with the given values replaced.
+    async fn create_test_block_detail(
+        height: u64,
+        unix_timestamp: i64,
+        num_transactions: u64,
+        size: u64,
+    ) -> BlockDetail<SeqTypes> {
+        let leaf = Leaf::genesis(&ValidatedState::default(), &NodeState::mock()).await;
+        let block_detail = create_block_detail_from_leaf(&leaf);
+
+        BlockDetail {
+            height,
+            time: Timestamp(OffsetDateTime::from_unix_timestamp(unix_timestamp).unwrap()),
+            num_transactions,
+            size,
+            ..block_detail
+        }
+    }
+
+    /// [create_test_leaf] creates a [Leaf] that is derived from the genesis
+    /// [Leaf], with its block height replaced by the given height.
+    async fn create_test_leaf(height: u64) -> Leaf {
+        let mut leaf = Leaf::genesis(&ValidatedState::default(), &NodeState::mock()).await;
+        *leaf.block_header_mut().height_mut() = height;
+        leaf
+    }
+
+    /// [create_test_fee_account] creates a [FeeAccount] whose address is
+    /// derived from the given index.
+    fn create_test_fee_account(index: u64) -> FeeAccount {
+        format!("0x{:040x}", index).parse().unwrap()
+    }
+
+    /// [create_test_stake_table] creates a [StakeTable] with an entry for each
+    /// of the given stakes.  The table is advanced so that the entries are
+    /// present for every [SnapshotVersion].
+    fn create_test_stake_table(
+        stakes: &[u64],
+    ) -> (
+        Vec<BLSPubKey>,
+        StakeTable<BLSPubKey, StateVerKey, CircuitField>,
+    ) {
+        let mut stake_table = StakeTable::new(stakes.len());
+        let public_keys = stakes
+            .iter()
+            .enumerate()
+            .map(|(index, stake)| {
+                let public_key = BLSPubKey::generated_from_seed_indexed([0; 32], index as u64).0;
+                let state_key = StateKeyPair::generate_from_seed_indexed([0; 32], index as u64);
+                stake_table
+                    .register(public_key, U256::from(*stake), state_key.ver_key())
+                    .expect("registering stake table entry");
+                public_key
+            })
+            .collect();
+
+        stake_table.advance();
+        stake_table.advance();
+
+        (public_keys, stake_table)
+    }
+
+    #[async_std::test]
+    async fn test_process_leaf_error_debug() {
+        let (mut sender, receiver) = mpsc::channel(1);
+        // deliberately close the receiver.
+        drop(receiver);
+
+        // Attempt to receive, and we should get an error.
+        let receive_result = sender.send(1).await;
+
+        assert!(receive_result.is_err());
+        let err = receive_result.unwrap_err();
+
+        let process_leaf_err = super::ProcessLeafError::BlockSendError(err);
+
+        assert_eq!(
+            format!("{:?}", process_leaf_err),
+            "BlockSendError(SendError { kind: Disconnected })"
+        );
+    }
+
+    #[async_std::test]
+    async fn test_process_leaf_stream() {
+        let data_state: DataState = Default::default();
+        let data_state = Arc::new(RwLock::new(data_state));
+        let (block_sender, block_receiver) = futures::channel::mpsc::channel(1);
+        let (voters_sender, voters_receiver) = futures::channel::mpsc::channel(1);
+        let (leaf_sender, leaf_receiver) = futures::channel::mpsc::channel(1);
+
+        let mut process_leaf_stream_task_handle = ProcessLeafStreamTask::new(
+            leaf_receiver,
+            data_state.clone(),
+            block_sender,
+            voters_sender,
+        );
+
+        {
+            let data_state = data_state.read().await;
+            // Latest blocks should be empty
+            assert_eq!(data_state.latest_blocks().count(), 0);
+            // Latest voters should be empty
+            assert_eq!(data_state.latest_voters().count(), 0);
+        }
+
+        let validated_state = ValidatedState {
+            block_merkle_tree: BlockMerkleTree::new(32),
+            fee_merkle_tree: FeeMerkleTree::new(32),
+            chain_config: ChainConfig::default().into(),
+        };
+        let instance_state = NodeState::mock();
+
+        let sample_leaf = Leaf::genesis(&validated_state, &instance_state).await;
+
+        let mut leaf_sender = leaf_sender;
+        // We should be able to send a leaf without issue
+        assert_eq!(leaf_sender.send(sample_leaf).await, Ok(()),);
+
+        let mut block_receiver = block_receiver;
+        // We should receive a Block Detail.
+
+        let next_block = block_receiver.next().await;
+        assert!(next_block.is_some());
+
+        let mut voters_receiver = voters_receiver;
+        // We should receive a BitVec of voters.
+        let next_voters = voters_receiver.next().await;
+        assert!(next_voters.is_some());
+
+        {
+            let data_state = data_state.read().await;
+            // Latest blocks should now have a single entry
+            assert_eq!(data_state.latest_blocks().count(), 1);
+            // Latest voters should now have a single entry
+            assert_eq!(data_state.latest_voters().count(), 1);
+        }
+
+        // We explicitly drop these, as it should make the task clean up.
+        drop(block_receiver);
+        drop(leaf_sender);
+
+        assert_eq!(
+            process_leaf_stream_task_handle
+                .task_handle
+                .take()
+                .unwrap()
+                .timeout(Duration::from_millis(200))
+                .await,
+            Ok(())
+        );
+    }
+
+    #[async_std::test]
+    async fn test_process_leaf_stream_pause() {
+        let data_state = Arc::new(RwLock::new(DataState::default()));
+        let (block_sender, _block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
+        let (mut leaf_sender, leaf_receiver) = mpsc::channel(10);
+
+        let process_leaf_stream_task = ProcessLeafStreamTask::new(
+            leaf_receiver,
+            data_state.clone(),
+            block_sender,
+            voters_sender,
+        );
+
+        process_leaf_stream_task.pause();
+        assert!(process_leaf_stream_task.is_paused());
+
+        for height in 0..3 {
+            assert_eq!(
+                leaf_sender.send(create_test_leaf(height).await).await,
+                Ok(())
+            );
+        }
+
+        // Nothing should be processed while paused.
+        async_std::task::sleep(Duration::from_millis(50)).await;
+        assert_eq!(data_state.read().await.latest_blocks().count(), 0);
+
+        // Once resumed, the held leaves should be caught up on in order.
+        process_leaf_stream_task.resume();
+        assert_eq!(
+            super::wait_for_height(data_state.clone(), 2, Duration::from_secs(1)).await,
+            Ok(())
+        );
+
+        let heights = data_state
+            .read()
+            .await
+            .latest_blocks()
+            .map(|block| block.height)
+            .collect::<Vec<_>>();
+        assert_eq!(heights, vec![0, 1, 2]);
+    }
+
+    #[async_std::test]
+    async fn test_process_node_identity_stream() {
+        let data_state: DataState = Default::default();
+        let data_state = Arc::new(RwLock::new(data_state));
+        let (node_identity_sender_1, node_identity_receiver_1) = futures::channel::mpsc::channel(1);
+        let (node_identity_sender_2, node_identity_receiver_2) = futures::channel::mpsc::channel(1);
+
+        let mut process_node_identity_task_handle = ProcessNodeIdentityStreamTask::new(
+            node_identity_receiver_1,
+            data_state.clone(),
+            node_identity_sender_2,
+        );
+
+        {
+            let data_state = data_state.read().await;
+            // Latest blocks should be empty
+            assert_eq!(data_state.node_identity().count(), 0);
+        }
+
+        // Send a node update to the Stream
+        let public_key_1 = BLSPubKey::generated_from_seed_indexed([0; 32], 0).0;
+        let node_identity_1 = NodeIdentity::from_public_key(public_key_1);
+
+        let mut node_identity_sender_1 = node_identity_sender_1;
+        let mut node_identity_receiver_2 = node_identity_receiver_2;
+
+        assert_eq!(
+            node_identity_sender_1.send(node_identity_1.clone()).await,
+            Ok(())
+        );
+
+        assert_eq!(
+            node_identity_receiver_2.next().await,
+            Some(node_identity_1.clone())
+        );
+
+        {
+            let data_state = data_state.read().await;
+            // Latest blocks should now have a single entry
+            assert_eq!(data_state.node_identity().count(), 1);
+            assert_eq!(data_state.node_identity().next(), Some(&node_identity_1));
+        }
+
+        // If we send the same node identity again, we should not have a new entry.
+        assert_eq!(
+            node_identity_sender_1.send(node_identity_1.clone()).await,
+            Ok(())
+        );
+
+        assert_eq!(
+            node_identity_receiver_2.next().await,
+            Some(node_identity_1.clone())
+        );
+
+        {
+            let data_state = data_state.read().await;
+            // Latest blocks should now have a single entry
+            assert_eq!(data_state.node_identity().count(), 1);
+            assert_eq!(data_state.node_identity().next(), Some(&node_identity_1));
+        }
+
+        // If we send an update for that node instead, it should update the
+        // entry.
+        let node_identity_1 = NodeIdentity::new(
+            public_key_1,
+            Some("name".to_string()),
+            Some(Url::parse("https://example.com/").unwrap()),
+            Some("company".to_string()),
+            Some(Url::parse("https://example.com/").unwrap()),
+            Some(LocationDetails::new(
+                Some((40.7128, -74.0060)),
+                Some("US".to_string()),
+            )),
+            Some("operating_system".to_string()),
+            Some("node_type".to_string()),
+            Some("network_type".to_string()),
+        );
+        assert_eq!(
+            node_identity_sender_1.send(node_identity_1.clone()).await,
+            Ok(())
+        );
+
+        assert_eq!(
+            node_identity_receiver_2.next().await,
+            Some(node_identity_1.clone())
+        );
+
+        {
+            let data_state = data_state.read().await;
+            // Latest blocks should now have a single entry
+            assert_eq!(data_state.node_identity().count(), 1);
+            assert_eq!(data_state.node_identity().next(), Some(&node_identity_1));
+        }
+
+        // If we send a new node identity, it should result in a new node
+        // identity
+
+        let public_key_2 = BLSPubKey::generated_from_seed_indexed([0; 32], 1).0;
+        let node_identity_2 = NodeIdentity::from_public_key(public_key_2);
+
+        assert_eq!(
+            node_identity_sender_1.send(node_identity_2.clone()).await,
+            Ok(())
+        );
+
+        assert_eq!(
+            node_identity_receiver_2.next().await,
+            Some(node_identity_2.clone())
+        );
+
+        {
+            let data_state = data_state.read().await;
+            // Latest blocks should now have a single entry
+            assert_eq!(data_state.node_identity().count(), 2);
+            assert_eq!(data_state.node_identity().next(), Some(&node_identity_1));
+            assert_eq!(data_state.node_identity().last(), Some(&node_identity_2));
+        }
+
+        if let Some(process_node_identity_task_handle) =
+            process_node_identity_task_handle.task_handle.take()
+        {
+            assert_eq!(process_node_identity_task_handle.cancel().await, None);
+        }
+    }
+
+    #[test]
+    fn test_resolve_recipient_name() {
+        let mut data_state: DataState = Default::default();
+        let public_key_1 = BLSPubKey::generated_from_seed_indexed([0; 32], 0).0;
+        let node_identity_1 = NodeIdentity::new(
+            public_key_1,
+            Some("name".to_string()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        data_state.add_node_identity(node_identity_1.clone());
+
+        let recipient_1 = create_test_fee_account(1);
+        let recipient_2 = create_test_fee_account(2);
+        data_state.add_fee_recipient_identity(recipient_1, public_key_1);
+
+        assert_eq!(
+            data_state.resolve_recipient_identity(&recipient_1),
+            Some(&node_identity_1)
+        );
+        assert_eq!(
+            data_state.resolve_recipient_name(&recipient_1),
+            Some("name".to_string())
+        );
+
+        // An unknown recipient should fall through to the raw address.
+        assert_eq!(data_state.resolve_recipient_identity(&recipient_2), None);
+        assert_eq!(data_state.resolve_recipient_name(&recipient_2), None);
+    }
+
+    #[async_std::test]
+    async fn test_network_summary() {
+        let mut data_state: DataState = Default::default();
+        for index in 0..3 {
+            data_state.add_node_identity(NodeIdentity::from_public_key(
+                BLSPubKey::generated_from_seed_indexed([0; 32], index).0,
+            ));
+        }
+
+        data_state.add_latest_block(create_test_block_detail(1, 0, 5, 100).await);
+        data_state.add_latest_block(create_test_block_detail(2, 10, 10, 200).await);
+        data_state.add_latest_block(create_test_block_detail(3, 20, 10, 300).await);
+
+        let mut voters_1 = BitVec::<u16>::repeat(false, 3);
+        voters_1.set(0, true);
+        let mut voters_2 = BitVec::<u16>::repeat(false, 3);
+        voters_2.set(0, true);
+        voters_2.set(1, true);
+        data_state.add_latest_voters(voters_1);
+        data_state.add_latest_voters(voters_2);
+
+        let summary = data_state.network_summary();
+        assert_eq!(summary.latest_block_height, Some(3));
+        assert_eq!(summary.known_node_count, 3);
+        assert_eq!(summary.average_block_size, Some(200.0));
+        assert_eq!(summary.estimated_tps, Some(1.0));
+        assert_eq!(summary.distinct_voters, 2);
+        assert_eq!(summary.total_stake, Default::default());
+    }
+
+    #[async_std::test]
+    async fn test_process_incoming_leaf_duplicate() {
+        let data_state: DataState = Default::default();
+        let data_state = Arc::new(RwLock::new(data_state));
+        let (block_sender, mut block_receiver) = mpsc::channel(10);
+        let (voters_sender, mut voters_receiver) = mpsc::channel(10);
+
+        let leaf = Leaf::genesis(&ValidatedState::default(), &NodeState::mock()).await;
+
+        for _ in 0..2 {
+            assert!(super::process_incoming_leaf(
+                leaf.clone(),
+                OffsetDateTime::now_utc(),
+                data_state.clone(),
+                block_sender.clone(),
+                &mut vec![voters_sender.clone()],
+                None::<mpsc::Sender<VoterStakeSnapshot>>,
+            )
+            .await
+            .is_ok());
+        }
+
+        {
+            let data_state = data_state.read().await;
+            assert_eq!(data_state.latest_blocks().count(), 1);
+            assert_eq!(data_state.latest_voters().count(), 1);
+            assert_eq!(data_state.duplicate_leaves(), 1);
+        }
+
+        // Only the first leaf should have been sent along.
+        drop(block_sender);
+        drop(voters_sender);
+        assert_eq!(block_receiver.by_ref().count().await, 1);
+        assert_eq!(voters_receiver.by_ref().count().await, 1);
+    }
+
+    #[async_std::test]
+    async fn test_grafana_timeseries_json() {
+        let mut data_state: DataState = Default::default();
+        data_state.add_latest_block(create_test_block_detail(1, 10, 0, 100).await);
+        data_state.add_latest_block(create_test_block_detail(2, 12, 4, 250).await);
+
+        let timeseries = data_state.grafana_timeseries_json(&["size", "tps", "unknown"]);
+        assert_eq!(
+            timeseries,
+            serde_json::json!([
+                {
+                    "target": "size",
+                    "datapoints": [[100.0, 10_000], [250.0, 12_000]],
+                },
+                {
+                    "target": "tps",
+                    "datapoints": [[2.0, 12_000]],
+                },
+                {
+                    "target": "unknown",
+                    "datapoints": [],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_stake_table_json() {
+        let (public_keys, stake_table) = create_test_stake_table(&[1, 2]);
+        let data_state = DataState::new(Default::default(), Default::default(), stake_table);
+
+        let json = data_state.stake_table_json(SnapshotVersion::LastEpochStart);
+        let entries = json.as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["stake"], "1");
+        assert_eq!(entries[1]["stake"], "2");
+        assert_eq!(entries[0]["bls_key"], super::encode_hex(&public_keys[0]));
+        assert_eq!(entries[1]["bls_key"], super::encode_hex(&public_keys[1]));
+        assert!(entries[0]["state_key"].as_str().unwrap().starts_with("0x"));
+
+        // An empty stake table should be represented as an empty array.
+        let data_state: DataState = Default::default();
+        assert_eq!(
+            data_state.stake_table_json(SnapshotVersion::LastEpochStart),
+            serde_json::json!([])
+        );
+    }
+
+    /// [LevelCapturingSubscriber] is a [tracing::Subscriber] that records the
+    /// level of every event that it observes.
+    #[derive(Clone, Default)]
+    struct LevelCapturingSubscriber {
+        levels: Arc<std::sync::Mutex<Vec<tracing::Level>>>,
+    }
+
+    impl tracing::Subscriber for LevelCapturingSubscriber {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            self.levels.lock().unwrap().push(*event.metadata().level());
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[test]
+    fn test_process_leaf_stream_termination_log_levels() {
+        let leaf = async_std::task::block_on(Leaf::genesis(
+            &ValidatedState::default(),
+            &NodeState::mock(),
+        ));
+
+        // A clean end of stream should only be logged at info.
+        {
+            let subscriber = LevelCapturingSubscriber::default();
+            let levels = subscriber.levels.clone();
+            let data_state = Arc::new(RwLock::new(DataState::default()));
+            let (block_sender, _block_receiver) = mpsc::channel(1);
+            let (voters_sender, _voters_receiver) = mpsc::channel(1);
+
+            tracing::subscriber::with_default(subscriber, || {
+                async_std::task::block_on(ProcessLeafStreamTask::process_leaf_stream(
+                    futures::stream::empty(),
+                    data_state,
+                    block_sender,
+                    vec![voters_sender],
+                    None::<mpsc::Sender<VoterStakeSnapshot>>,
+                    Default::default(),
+                ))
+            });
+
+            let levels = levels.lock().unwrap();
+            assert!(levels.contains(&tracing::Level::INFO));
+            assert!(!levels.contains(&tracing::Level::ERROR));
+        }
+
+        // An error termination should be logged at error.
+        {
+            let subscriber = LevelCapturingSubscriber::default();
+            let levels = subscriber.levels.clone();
+            let data_state = Arc::new(RwLock::new(DataState::default()));
+            let (block_sender, block_receiver) = mpsc::channel(1);
+            let (voters_sender, _voters_receiver) = mpsc::channel(1);
+            drop(block_receiver);
+
+            let result = tracing::subscriber::with_default(subscriber, || {
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    async_std::task::block_on(ProcessLeafStreamTask::process_leaf_stream(
+                        futures::stream::iter(vec![leaf]),
+                        data_state,
+                        block_sender,
+                        vec![voters_sender],
+                        None::<mpsc::Sender<VoterStakeSnapshot>>,
+                        Default::default(),
+                    ))
+                }))
+            });
+
+            assert!(result.is_err());
+            assert!(levels.lock().unwrap().contains(&tracing::Level::ERROR));
+        }
+    }
+
+    #[async_std::test]
+    async fn test_process_leaf_stream_voter_stake_snapshot() {
+        let (_, stake_table) = create_test_stake_table(&[1, 2, 3]);
+        let data_state = DataState::new(Default::default(), Default::default(), stake_table);
+        let data_state = Arc::new(RwLock::new(data_state));
+        let (block_sender, _block_receiver) = mpsc::channel(1);
+        let (voters_sender, _voters_receiver) = mpsc::channel(1);
+        let (voter_stake_sender, mut voter_stake_receiver) = mpsc::channel(1);
+        let (mut leaf_sender, leaf_receiver) = mpsc::channel(1);
+
+        let _process_leaf_stream_task_handle = ProcessLeafStreamTask::new_with_voter_stake_sender(
+            leaf_receiver,
+            data_state.clone(),
+            block_sender,
+            voters_sender,
+            voter_stake_sender,
+        );
+
+        let leaf = Leaf::genesis(&ValidatedState::default(), &NodeState::mock()).await;
+        assert_eq!(leaf_sender.send(leaf).await, Ok(()));
+
+        // The genesis leaf has no signatures, so none of the stake has
+        // participated.
+        assert_eq!(
+            voter_stake_receiver.next().await,
+            Some(VoterStakeSnapshot {
+                height: 0,
+                participating_stake: U256::zero(),
+                total_stake: U256::from(6),
+            })
+        );
+    }
+
+    #[async_std::test]
+    async fn test_process_leaf_stream_multiple_voters_senders() {
+        let data_state = Arc::new(RwLock::new(DataState::default()));
+        let (block_sender, mut block_receiver) = mpsc::channel(10);
+        let (voters_sender_1, mut voters_receiver_1) = mpsc::channel(10);
+        let (voters_sender_2, mut voters_receiver_2) = mpsc::channel(10);
+        let (mut leaf_sender, leaf_receiver) = mpsc::channel(10);
+
+        let _process_leaf_stream_task_handle = ProcessLeafStreamTask::new_with_voters_senders(
+            leaf_receiver,
+            data_state.clone(),
+            block_sender,
+            vec![voters_sender_1, voters_sender_2],
+        );
+
+        assert_eq!(leaf_sender.send(create_test_leaf(1).await).await, Ok(()));
+        assert!(block_receiver.next().await.is_some());
+
+        // Both of the voters receivers should receive the voters.
+        assert!(voters_receiver_1.next().await.is_some());
+        assert!(voters_receiver_2.next().await.is_some());
+
+        // A failing voters sender should not affect the others.
+        drop(voters_receiver_1);
+        assert_eq!(leaf_sender.send(create_test_leaf(2).await).await, Ok(()));
+        assert!(block_receiver.next().await.is_some());
+        assert!(voters_receiver_2.next().await.is_some());
+
+        assert_eq!(leaf_sender.send(create_test_leaf(3).await).await, Ok(()));
+        assert!(block_receiver.next().await.is_some());
+        assert!(voters_receiver_2.next().await.is_some());
+        assert_eq!(data_state.read().await.latest_blocks().count(), 3);
+    }
+
+    /// [create_test_voters] creates a voters [BitVec] of the given length with
+    /// the given indices set.
+    fn create_test_voters(len: usize, indices: &[usize]) -> BitVec<u16> {
+        let mut voters = BitVec::<u16>::repeat(false, len);
+        for index in indices {
+            voters.set(*index, true);
+        }
+        voters
+    }
+
+    #[async_std::test]
+    async fn test_voter_similarity() {
+        let mut data_state: DataState = Default::default();
+        data_state.add_latest_block(create_test_block_detail(1, 0, 0, 0).await);
+        data_state.add_latest_voters(create_test_voters(4, &[0, 1, 2]));
+        data_state.add_latest_block(create_test_block_detail(2, 1, 0, 0).await);
+        data_state.add_latest_voters(create_test_voters(4, &[1, 2, 3]));
+
+        // The sets share two of the four nodes that voted.
+        assert_eq!(data_state.voter_similarity(1, 2), Some(0.5));
+        assert_eq!(data_state.voter_similarity(2, 1), Some(0.5));
+        assert_eq!(data_state.voter_similarity(1, 1), Some(1.0));
+
+        // Heights that are not retained cannot be compared.
+        assert_eq!(data_state.voter_similarity(1, 3), None);
+        assert_eq!(data_state.voter_similarity(3, 2), None);
+    }
+
+    #[async_std::test]
+    async fn test_recent_leaves() {
+        let data_state = Arc::new(RwLock::new(DataState::default()));
+        let (block_sender, _block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
+
+        // Leaves should not be retained by default.
+        assert!(super::process_incoming_leaf(
+            create_test_leaf(1).await,
+            OffsetDateTime::now_utc(),
+            data_state.clone(),
+            block_sender.clone(),
+            &mut vec![voters_sender.clone()],
+            None::<mpsc::Sender<VoterStakeSnapshot>>,
+        )
+        .await
+        .is_ok());
+        assert_eq!(data_state.read().await.recent_leaves().count(), 0);
+
+        data_state.write().await.set_recent_leaves_capacity(2);
+        for height in 2..5 {
+            assert!(super::process_incoming_leaf(
+                create_test_leaf(height).await,
+                OffsetDateTime::now_utc(),
+                data_state.clone(),
+                block_sender.clone(),
+                &mut vec![voters_sender.clone()],
+                None::<mpsc::Sender<VoterStakeSnapshot>>,
+            )
+            .await
+            .is_ok());
+        }
+
+        // Only the most recent leaves up to the capacity should be retained.
+        let data_state = data_state.read().await;
+        assert_eq!(
+            data_state
+                .recent_leaves()
+                .map(|leaf| leaf.height())
+                .collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+    }
+
+    #[test]
+    fn test_unknown_stake_table_entries() {
+        let (public_keys, stake_table) = create_test_stake_table(&[1, 2, 3]);
+        let mut data_state: DataState = Default::default();
+        data_state.stake_table = stake_table;
+        data_state.add_node_identity(NodeIdentity::from_public_key(public_keys[0]));
+        data_state.add_node_identity(NodeIdentity::from_public_key(public_keys[2]));
+
+        assert_eq!(
+            data_state.unknown_stake_table_entries(),
+            vec![public_keys[1]]
+        );
+
+        // Once the identity is known, there should be no more gaps.
+        data_state.add_node_identity(NodeIdentity::from_public_key(public_keys[1]));
+        assert_eq!(data_state.unknown_stake_table_entries(), vec![]);
+    }
+
+    #[async_std::test]
+    async fn test_block_size_std_dev() {
+        let mut data_state: DataState = Default::default();
+        assert_eq!(data_state.block_size_std_dev(), None);
+
+        data_state.add_latest_block(create_test_block_detail(0, 0, 0, 2).await);
+        assert_eq!(data_state.block_size_std_dev(), None);
+
+        for (height, size) in [4, 4, 4, 5, 5, 7, 9].into_iter().enumerate() {
+            data_state
+                .add_latest_block(create_test_block_detail(height as u64 + 1, 0, 0, size).await);
+        }
+
+        // This distribution has a mean of 5 and a population standard
+        // deviation of 2.
+        let std_dev = data_state.block_size_std_dev().unwrap();
+        assert!((std_dev - 2.0).abs() < 1e-9);
+    }
+
+    #[async_std::test]
+    async fn test_empty_block_ratio() {
+        let mut data_state: DataState = Default::default();
+        assert_eq!(data_state.empty_block_ratio(), None);
+
+        data_state.add_latest_block(create_test_block_detail(1, 0, 0, 0).await);
+        data_state.add_latest_block(create_test_block_detail(2, 0, 3, 30).await);
+        data_state.add_latest_block(create_test_block_detail(3, 0, 0, 0).await);
+        data_state.add_latest_block(create_test_block_detail(4, 0, 1, 10).await);
+
+        assert_eq!(data_state.empty_block_ratio(), Some(0.5));
+    }
+
+    #[async_std::test]
+    async fn test_block_rows() {
+        let mut data_state: DataState = Default::default();
+        let block_1 = create_test_block_detail(1, 0, 2, 20).await;
+        let block_1_hash = block_1.hash;
+        data_state.add_latest_block(block_1);
+        data_state.add_latest_voters(create_test_voters(3, &[0, 2]));
+        data_state.add_latest_voter_stake(VoterStakeSnapshot {
+            height: 1,
+            participating_stake: U256::from(5),
+            total_stake: U256::from(6),
+        });
+
+        // A block without voter stake information should still be reported.
+        data_state.add_latest_block(create_test_block_detail(2, 1, 0, 0).await);
+        data_state.add_latest_voters(create_test_voters(3, &[]));
+
+        let rows = data_state.block_rows();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[0],
+            super::BlockRow {
+                height: 1,
+                hash: block_1_hash,
+                num_transactions: 2,
+                size: 20,
+                voter_count: Some(2),
+                participating_stake: Some(U256::from(5)),
+            }
+        );
+        assert_eq!(rows[1].height, 2);
+        assert_eq!(rows[1].voter_count, Some(0));
+        assert_eq!(rows[1].participating_stake, None);
+    }
+
+    #[async_std::test]
+    async fn test_unequal_capacities() {
+        // Retain more voters than blocks.
+        let mut data_state: DataState = Default::default();
+        data_state.set_latest_blocks_capacity(2);
+        data_state.set_latest_voters_capacity(4);
+        for height in 1..=4 {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+            data_state.add_latest_voters(create_test_voters(
+                4,
+                &(0..height as usize).collect::<Vec<_>>(),
+            ));
+        }
+
+        assert_eq!(data_state.latest_blocks().count(), 2);
+        assert_eq!(data_state.latest_voters().count(), 4);
+        assert_eq!(
+            data_state
+                .block_rows()
+                .iter()
+                .map(|row| (row.height, row.voter_count))
+                .collect::<Vec<_>>(),
+            vec![(3, Some(3)), (4, Some(4))]
+        );
+        assert_eq!(
+            data_state
+                .voters_for_height(3)
+                .map(|voters| voters.count_ones()),
+            Some(3)
+        );
+
+        // Retain more blocks than voters.
+        let mut data_state: DataState = Default::default();
+        data_state.set_latest_blocks_capacity(4);
+        data_state.set_latest_voters_capacity(2);
+        for height in 1..=4 {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+            data_state.add_latest_voters(create_test_voters(
+                4,
+                &(0..height as usize).collect::<Vec<_>>(),
+            ));
+        }
+
+        assert_eq!(
+            data_state
+                .block_rows()
+                .iter()
+                .map(|row| (row.height, row.voter_count))
+                .collect::<Vec<_>>(),
+            vec![(1, None), (2, None), (3, Some(3)), (4, Some(4))]
+        );
+        assert_eq!(data_state.voters_for_height(2), None);
+        assert_eq!(
+            data_state
+                .voters_for_height(4)
+                .map(|voters| voters.count_ones()),
+            Some(4)
+        );
+    }
+
+    #[async_std::test]
+    async fn test_oldest_block() {
+        let mut data_state: DataState = Default::default();
+        data_state.set_latest_blocks_capacity(3);
+        assert!(data_state.oldest_block().is_none());
+        assert_eq!(data_state.oldest_block_height(), None);
+        assert_eq!(data_state.latest_block_height(), None);
+
+        for height in 1..=5 {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+        }
+
+        // The first two blocks should have been evicted.
+        assert_eq!(data_state.oldest_block().map(|block| block.height), Some(3));
+        assert_eq!(data_state.oldest_block_height(), Some(3));
+        assert_eq!(data_state.latest_block_height(), Some(5));
+    }
+
+    #[async_std::test]
+    async fn test_cumulative_counters_replay() {
+        let mut data_state: DataState = Default::default();
+        for height in 1..=3 {
+            data_state.update_cumulative_counters(&create_test_block_detail(height, 0, 2, 0).await);
+        }
+
+        // Replaying an overlapping range should only count the new heights.
+        for height in 2..=4 {
+            data_state.update_cumulative_counters(&create_test_block_detail(height, 0, 2, 0).await);
+        }
+
+        assert_eq!(data_state.cumulative_blocks(), 4);
+        assert_eq!(data_state.cumulative_transactions(), 8);
+        assert_eq!(data_state.highest_counted_height(), Some(4));
+    }
+
+    /// [MockVoterAttributionSource] attributes every [Leaf] to the same
+    /// voters.
+    struct MockVoterAttributionSource(BitVec<u16>);
+
+    impl VoterAttributionSource for MockVoterAttributionSource {
+        fn voters(&self, _leaf: &Leaf) -> BitVec<u16> {
+            self.0.clone()
+        }
+    }
+
+    #[async_std::test]
+    async fn test_voter_attribution_source() {
+        let (_, stake_table) = create_test_stake_table(&[1, 2, 3]);
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+        let (block_sender, _block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
+
+        // The genesis Quorum Certificate has no signatures.
+        let leaf = create_test_leaf(1).await;
+        assert!(super::QuorumCertificateVoters.voters(&leaf).is_empty());
+
+        data_state.set_voter_attribution_source(Arc::new(MockVoterAttributionSource(
+            create_test_voters(3, &[0, 2]),
+        )));
+        let data_state = Arc::new(RwLock::new(data_state));
+
+        assert!(super::process_incoming_leaf(
+            leaf,
+            OffsetDateTime::now_utc(),
+            data_state.clone(),
+            block_sender,
+            &mut vec![voters_sender],
+            None::<mpsc::Sender<VoterStakeSnapshot>>,
+        )
+        .await
+        .is_ok());
+
+        let data_state = data_state.read().await;
+        assert_eq!(
+            data_state.latest_voters().collect::<Vec<_>>(),
+            vec![&create_test_voters(3, &[0, 2])]
+        );
+        assert_eq!(
+            data_state
+                .latest_voter_stake()
+                .map(|voter_stake| voter_stake.participating_stake)
+                .collect::<Vec<_>>(),
+            vec![U256::from(4)]
+        );
+    }
+
+    #[async_std::test]
+    async fn test_process_incoming_leaf_cumulative_counters_replay() {
+        let data_state = Arc::new(RwLock::new(DataState::default()));
+        let (block_sender, _block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
+
+        for height in [0, 1, 2, 1, 2, 3] {
+            assert!(super::process_incoming_leaf(
+                create_test_leaf(height).await,
+                OffsetDateTime::now_utc(),
+                data_state.clone(),
+                block_sender.clone(),
+                &mut vec![voters_sender.clone()],
+                None::<mpsc::Sender<VoterStakeSnapshot>>,
+            )
+            .await
+            .is_ok());
+        }
+
+        let data_state = data_state.read().await;
+        assert_eq!(data_state.cumulative_blocks(), 4);
+        assert_eq!(data_state.highest_counted_height(), Some(3));
+    }
+
+    #[async_std::test]
+    async fn test_recent_proposers() {
+        let mut data_state: DataState = Default::default();
+        let public_key_1 = BLSPubKey::generated_from_seed_indexed([0; 32], 0).0;
+        let public_key_2 = BLSPubKey::generated_from_seed_indexed([0; 32], 1).0;
+        data_state.add_fee_recipient_identity(create_test_fee_account(1), public_key_1);
+        data_state.add_fee_recipient_identity(create_test_fee_account(2), public_key_2);
+
+        for (height, proposer) in [(1, 1), (2, 2), (3, 1), (4, 3)] {
+            let mut block = create_test_block_detail(height, 0, 0, 0).await;
+            block.proposer_id = vec![create_test_fee_account(proposer)];
+            data_state.add_latest_block(block);
+        }
+
+        // The proposer without a known identity should not be included.
+        let recent_proposers = data_state.recent_proposers();
+        assert_eq!(recent_proposers.len(), 2);
+        assert_eq!(recent_proposers.get(&public_key_1), Some(&3));
+        assert_eq!(recent_proposers.get(&public_key_2), Some(&2));
+    }
+
+    #[async_std::test]
+    async fn test_archive_sampling_stride() {
+        let mut data_state: DataState = Default::default();
+        data_state.set_latest_blocks_capacity(4);
+        data_state.set_archived_blocks_capacity(10);
+        data_state.set_archive_sampling_stride(3);
+
+        for height in 0..10 {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+        }
+
+        // Only every third block should be archived.
+        assert_eq!(
+            data_state
+                .archived_blocks()
+                .map(|block| block.height)
+                .collect::<Vec<_>>(),
+            vec![0, 3, 6, 9]
+        );
+
+        // The recent blocks should remain unsampled.
+        assert_eq!(
+            data_state
+                .latest_blocks()
+                .map(|block| block.height)
+                .collect::<Vec<_>>(),
+            vec![6, 7, 8, 9]
+        );
+    }
+
+    #[test]
+    fn test_compute_voters_bitvec_without_node_identity() {
+        let stake_table_voters = create_test_voters(4, &[0, 2]);
+        let voters_set = [0, 2]
+            .into_iter()
+            .map(|index| BLSPubKey::generated_from_seed_indexed([0; 32], index).0)
+            .collect::<std::collections::HashSet<_>>();
+
+        // Without any known identities, the voters should be retained in
+        // stake table order, rather than appearing as though nobody voted.
+        assert_eq!(
+            super::compute_voters_bitvec(&[], &voters_set, stake_table_voters.clone()),
+            stake_table_voters
+        );
+
+        // Once identities are known, the voters are remapped into the order
+        // of the identities.
+        let node_identity = [2, 1, 0]
+            .into_iter()
+            .map(|index| {
+                NodeIdentity::from_public_key(
+                    BLSPubKey::generated_from_seed_indexed([0; 32], index).0,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            super::compute_voters_bitvec(&node_identity, &voters_set, stake_table_voters),
+            create_test_voters(3, &[0, 2])
+        );
+    }
+
+    #[async_std::test]
+    async fn test_process_incoming_leaf_without_node_identity() {
+        let (_, stake_table) = create_test_stake_table(&[1, 2, 3]);
+        let data_state = DataState::new(Default::default(), Default::default(), stake_table);
+        assert!(data_state.node_identity().next().is_none());
+        let data_state = Arc::new(RwLock::new(data_state));
+        let (block_sender, _block_receiver) = mpsc::channel(1);
+        let (voters_sender, mut voters_receiver) = mpsc::channel(1);
+
+        let leaf = create_test_leaf(1).await;
+        assert!(super::process_incoming_leaf(
+            leaf,
+            OffsetDateTime::now_utc(),
+            data_state.clone(),
+            block_sender,
+            &mut vec![voters_sender],
+            None::<mpsc::Sender<VoterStakeSnapshot>>,
+        )
+        .await
+        .is_ok());
+
+        assert_eq!(voters_receiver.next().await, Some(BitVec::new()));
+        assert_eq!(data_state.read().await.latest_voters().count(), 1);
+    }
+
+    #[async_std::test]
+    async fn test_render_timestamp_offsets() {
+        let block = create_test_block_detail(1, 1_700_000_000, 0, 0).await;
+
+        assert_eq!(
+            super::render_timestamp(&block.time, 0),
+            "2023-11-14T22:13:20Z"
+        );
+        assert_eq!(
+            super::render_timestamp(&block.time, 2 * 60 * 60),
+            "2023-11-15T00:13:20+02:00"
+        );
+
+        // Invalid offsets should fall back to UTC.
+        assert_eq!(
+            super::render_timestamp(&block.time, i32::MAX),
+            "2023-11-14T22:13:20Z"
+        );
+
+        let mut data_state: DataState = Default::default();
+        data_state.add_latest_block(block);
+        assert_eq!(
+            data_state.latest_blocks_json(-5 * 60 * 60)[0]["time"],
+            "2023-11-14T17:13:20-05:00"
+        );
+    }
+
+    #[async_std::test]
+    async fn test_archived_block_by_height() {
+        let mut data_state: DataState = Default::default();
+        data_state.set_archived_blocks_capacity(8);
+        data_state.set_archive_sampling_stride(2);
+
+        for height in 0..100 {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+        }
+
+        // Only the last 8 sampled blocks should remain, and all of them
+        // should be found by height.
+        for height in (0..100).step_by(2) {
+            let block = data_state.archived_block_by_height(height);
+            if height >= 84 {
+                assert_eq!(block.map(|block| block.height), Some(height));
+            } else {
+                assert!(block.is_none());
+            }
+        }
+        assert!(data_state.archived_block_by_height(99).is_none());
+
+        // Shrinking the archive should keep the index consistent.
+        data_state.set_archived_blocks_capacity(3);
+        assert!(data_state.archived_block_by_height(92).is_none());
+        for height in [94, 96, 98] {
+            assert_eq!(
+                data_state
+                    .archived_block_by_height(height)
+                    .map(|block| block.height),
+                Some(height)
+            );
+        }
+
+        // Archiving a block with an already indexed height should not cause
+        // the index to be lost when the older entry is evicted.
+        data_state.add_latest_block(create_test_block_detail(96, 1, 0, 0).await);
+        data_state.add_latest_block(create_test_block_detail(100, 0, 0, 0).await);
+        assert_eq!(
+            data_state
+                .archived_block_by_height(96)
+                .map(|block| block.time.0.unix_timestamp()),
+            Some(1)
+        );
+    }
+
+    #[async_std::test]
+    async fn test_process_incoming_leaf_fork_event() {
+        let data_state: DataState = Default::default();
+        let data_state = Arc::new(RwLock::new(data_state));
+        let (block_sender, _block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
+
+        let leaf_a = create_test_leaf(1).await;
+        let mut leaf_b = create_test_leaf(1).await;
+        *leaf_b.block_header_mut().timestamp_mut() += 1;
+
+        let hash_a = create_block_detail_from_leaf(&leaf_a).hash;
+        let hash_b = create_block_detail_from_leaf(&leaf_b).hash;
+        assert_ne!(hash_a, hash_b);
+
+        for leaf in [leaf_a, leaf_b] {
+            assert!(super::process_incoming_leaf(
+                leaf,
+                OffsetDateTime::now_utc(),
+                data_state.clone(),
+                block_sender.clone(),
+                &mut vec![voters_sender.clone()],
+                None::<mpsc::Sender<VoterStakeSnapshot>>,
+            )
+            .await
+            .is_ok());
+        }
+
+        let data_state = data_state.read().await;
+        assert_eq!(
+            data_state.fork_events().collect::<Vec<_>>(),
+            vec![&ForkEvent {
+                height: 1,
+                hash_a,
+                hash_b,
+            }]
+        );
+
+        // The block that was observed first should be retained.
+        assert_eq!(
+            data_state
+                .latest_blocks()
+                .map(|block| block.hash)
+                .collect::<Vec<_>>(),
+            vec![hash_a]
+        );
+    }
+
+    #[async_std::test]
+    async fn test_process_incoming_leaf_fork_anomaly_event() {
+        let (anomaly_event_sender, mut anomaly_event_receiver) = mpsc::channel(10);
+        let mut data_state: DataState = Default::default();
+        data_state.set_anomaly_event_sender(Some(anomaly_event_sender));
+        let data_state = Arc::new(RwLock::new(data_state));
+        let (block_sender, _block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
+
+        let leaf_a = create_test_leaf(0).await;
+        let mut leaf_b = create_test_leaf(0).await;
+        *leaf_b.block_header_mut().timestamp_mut() += 1;
+
+        let hash_a = create_block_detail_from_leaf(&leaf_a).hash;
+        let hash_b = create_block_detail_from_leaf(&leaf_b).hash;
+
+        for leaf in [leaf_a, leaf_b] {
+            assert!(super::process_incoming_leaf(
+                leaf,
+                OffsetDateTime::now_utc(),
+                data_state.clone(),
+                block_sender.clone(),
+                &mut vec![voters_sender.clone()],
+                None::<mpsc::Sender<VoterStakeSnapshot>>,
+            )
+            .await
+            .is_ok());
+        }
+
+        data_state.write().await.set_anomaly_event_sender(None);
+        assert_eq!(
+            anomaly_event_receiver.next().await,
+            Some(AnomalyEvent::Fork(ForkEvent {
+                height: 0,
+                hash_a,
+                hash_b,
+            }))
+        );
+        assert_eq!(anomaly_event_receiver.next().await, None);
+    }
+
+    #[async_std::test]
+    async fn test_evicted_block_sender() {
+        let mut data_state: DataState = Default::default();
+        let (evicted_block_sender, mut evicted_block_receiver) = mpsc::channel(10);
+        data_state.set_latest_blocks_capacity(3);
+        data_state.set_evicted_block_sender(Some(evicted_block_sender));
+
+        for height in 0..5 {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+        }
+
+        // Shrinking the buffer should also notify of the evicted blocks.
+        data_state.set_latest_blocks_capacity(2);
+        data_state.set_evicted_block_sender(None);
+
+        assert_eq!(
+            evicted_block_receiver
+                .map(|block| block.height)
+                .collect::<Vec<_>>()
+                .await,
+            vec![0, 1, 2]
+        );
+    }
+
+    #[async_std::test]
+    async fn test_blocks_in_last() {
+        let mut data_state: DataState = Default::default();
+        for (height, unix_timestamp) in [0, 100, 200, 290, 300, 310].into_iter().enumerate() {
+            data_state.add_latest_block(
+                create_test_block_detail(height as u64, unix_timestamp, 0, 0).await,
+            );
+        }
+
+        let now = OffsetDateTime::from_unix_timestamp(300).unwrap();
+        assert_eq!(data_state.blocks_in_last(Duration::from_secs(100), now), 3);
+        assert_eq!(data_state.blocks_in_last(Duration::from_secs(10), now), 2);
+        assert_eq!(data_state.blocks_in_last(Duration::ZERO, now), 1);
+        assert_eq!(
+            data_state.blocks_in_last(Duration::from_secs(1_000), now),
+            5
+        );
+        assert_eq!(data_state.blocks_in_last(Duration::MAX, now), 5);
+    }
+
+    #[async_std::test]
+    async fn test_debug_dump() {
+        let (public_keys, stake_table) = create_test_stake_table(&[1, 2]);
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+        let mut node_identity = NodeIdentity::from_public_key(public_keys[0]);
+        node_identity.name = Some("node 0".to_string());
+        data_state.add_node_identity(node_identity);
+        data_state.add_latest_block(create_test_block_detail(1, 0, 0, 0).await);
+        data_state.add_latest_voters(BitVec::new());
+
+        let debug_dump = data_state.debug_dump();
+        let keys = debug_dump
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(
+            keys,
+            [
+                "blocks",
+                "network_summary",
+                "node_identities",
+                "stake_table",
+                "version",
+                "voters",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect()
+        );
+        assert_eq!(debug_dump["version"], super::DEBUG_DUMP_VERSION);
+        assert_eq!(debug_dump["voters"], serde_json::json!([[false]]));
+        assert_eq!(debug_dump["node_identities"][0]["name"], "node 0");
+
+        let anonymized_debug_dump = data_state.anonymized_debug_dump();
+        assert_eq!(
+            anonymized_debug_dump["node_identities"][0]["name"],
+            serde_json::Value::Null
+        );
+    }
+
+    #[async_std::test]
+    async fn test_proposer_gini() {
+        let mut even_data_state: DataState = Default::default();
+        let mut skewed_data_state: DataState = Default::default();
+        assert_eq!(even_data_state.proposer_gini(), None);
+
+        for (height, (even_proposer, skewed_proposer)) in [
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (1, 2),
+            (2, 3),
+            (3, 1),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut block = create_test_block_detail(height as u64, 0, 0, 0).await;
+            block.proposer_id = vec![create_test_fee_account(even_proposer)];
+            even_data_state.add_latest_block(block);
+
+            let mut block = create_test_block_detail(height as u64, 0, 0, 0).await;
+            block.proposer_id = vec![create_test_fee_account(skewed_proposer)];
+            skewed_data_state.add_latest_block(block);
+        }
+
+        let even_gini = even_data_state.proposer_gini().unwrap();
+        let skewed_gini = skewed_data_state.proposer_gini().unwrap();
+        assert!(even_gini.abs() < 1e-9);
+        assert!(skewed_gini > even_gini);
+    }
+
+    #[async_std::test]
+    async fn test_top_proposer_share() {
+        let mut data_state: DataState = Default::default();
+        assert_eq!(data_state.top_proposer_share(), None);
+
+        for (height, proposer) in [1, 1, 2, 1].into_iter().enumerate() {
+            let mut block = create_test_block_detail(height as u64, 0, 0, 0).await;
+            block.proposer_id = vec![create_test_fee_account(proposer)];
+            data_state.add_latest_block(block);
+        }
+
+        assert_eq!(data_state.top_proposer_share(), Some(0.75));
+    }
+
+    #[test]
+    fn test_voting_pattern() {
+        let mut data_state: DataState = Default::default();
+        let public_keys = (0..3)
+            .map(|index| BLSPubKey::generated_from_seed_indexed([0; 32], index).0)
+            .collect::<Vec<_>>();
+        for public_key in public_keys.iter().take(2) {
+            data_state.add_node_identity(NodeIdentity::from_public_key(*public_key));
+        }
+
+        data_state.add_latest_voters(create_test_voters(1, &[0]));
+        data_state.add_latest_voters(create_test_voters(2, &[1]));
+        data_state.add_latest_voters(create_test_voters(2, &[0, 1]));
+        data_state.add_latest_voters(create_test_voters(2, &[]));
+
+        assert_eq!(
+            data_state.voting_pattern(&public_keys[0], 3),
+            Some(vec![false, true, false])
+        );
+
+        // The voters that were recorded before the second node was known are
+        // shorter, and should be treated as not having voted.
+        assert_eq!(
+            data_state.voting_pattern(&public_keys[1], 10),
+            Some(vec![false, true, true, false])
+        );
+
+        assert_eq!(data_state.voting_pattern(&public_keys[2], 3), None);
+    }
+
+    #[test]
+    fn test_participation_rate_excluding_warm_up() {
+        let mut data_state: DataState = Default::default();
+        let public_keys = (0..3)
+            .map(|index| BLSPubKey::generated_from_seed_indexed([0; 32], index).0)
+            .collect::<Vec<_>>();
+        for public_key in public_keys.iter().take(2) {
+            data_state.add_node_identity(NodeIdentity::from_public_key(*public_key));
+        }
+
+        data_state.add_latest_voters(create_test_voters(2, &[0, 1]));
+        data_state.add_latest_voters(create_test_voters(2, &[0]));
+
+        // The third node is added mid-window.
+        data_state.add_node_identity(NodeIdentity::from_public_key(public_keys[2]));
+        assert_eq!(data_state.first_seen_index(&public_keys[2]), None);
+        assert_eq!(
+            data_state.participation_rate_excluding_warm_up(&public_keys[2]),
+            None
+        );
+
+        data_state.add_latest_voters(create_test_voters(3, &[0, 2]));
+        data_state.add_latest_voters(create_test_voters(3, &[1, 2]));
+
+        assert_eq!(data_state.first_seen_index(&public_keys[0]), Some(0));
+        assert_eq!(data_state.first_seen_index(&public_keys[2]), Some(2));
+
+        // The blocks that predate the third node are not counted against it.
+        assert_eq!(data_state.participation_rate(&public_keys[2]), Some(0.5));
+        assert_eq!(
+            data_state.participation_rate_excluding_warm_up(&public_keys[2]),
+            Some(1.0)
+        );
+        assert_eq!(
+            data_state.participation_rate_excluding_warm_up(&public_keys[1]),
+            data_state.participation_rate(&public_keys[1])
+        );
+
+        // Once the earlier voters have been evicted, the index is relative to
+        // the oldest retained voters.
+        data_state.set_latest_voters_capacity(3);
+        assert_eq!(data_state.first_seen_index(&public_keys[0]), Some(0));
+        assert_eq!(data_state.first_seen_index(&public_keys[2]), Some(1));
+        assert_eq!(
+            data_state.participation_rate_excluding_warm_up(&public_keys[2]),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn test_stake_of() {
+        let (public_keys, stake_table) = create_test_stake_table(&[10, 20]);
+        let data_state = DataState::new(Default::default(), Default::default(), stake_table);
+
+        assert_eq!(
+            data_state.stake_of(&public_keys[0], SnapshotVersion::Head),
+            Some(U256::from(10))
+        );
+        assert_eq!(
+            data_state.stake_of(&public_keys[1], SnapshotVersion::LastEpochStart),
+            Some(U256::from(20))
+        );
+
+        let unknown_public_key = BLSPubKey::generated_from_seed_indexed([0; 32], 2).0;
+        assert_eq!(
+            data_state.stake_of(&unknown_public_key, SnapshotVersion::Head),
+            None
+        );
+    }
+
+    #[async_std::test]
+    async fn test_median_block_reward() {
+        let mut data_state: DataState = Default::default();
+        assert_eq!(data_state.median_block_reward(), None);
+
+        let rewards: [&[u64]; 4] = [&[5], &[1, 2], &[10], &[]];
+        for (height, rewards) in rewards.into_iter().enumerate() {
+            data_state.add_latest_block(BlockDetail {
+                block_reward: rewards
+                    .iter()
+                    .map(|reward| FeeAmount(U256::from(*reward)))
+                    .collect(),
+                ..create_test_block_detail(height as u64, 0, 0, 0).await
+            });
+        }
+
+        // The summed rewards are 0, 3, 5, and 10, so the middle two are
+        // averaged.
+        assert_eq!(data_state.median_block_reward(), Some(U256::from(4)));
+
+        data_state.add_latest_block(BlockDetail {
+            block_reward: vec![FeeAmount(U256::from(7))],
+            ..create_test_block_detail(4, 0, 0, 0).await
+        });
+        assert_eq!(data_state.median_block_reward(), Some(U256::from(5)));
+    }
+
+    #[async_std::test]
+    async fn test_top_blocks_by_transactions() {
+        let mut data_state: DataState = Default::default();
+        assert!(data_state.top_blocks_by_transactions(3).is_empty());
+
+        for (height, num_transactions) in [(1, 3), (2, 7), (3, 1), (4, 7), (5, 0)] {
+            data_state
+                .add_latest_block(create_test_block_detail(height, 0, num_transactions, 0).await);
+        }
+
+        let top_heights = |n| {
+            data_state
+                .top_blocks_by_transactions(n)
+                .iter()
+                .map(|block| block.height)
+                .collect::<Vec<_>>()
+        };
+
+        // Blocks 2 and 4 are tied, so the most recent should come first.
+        assert_eq!(top_heights(3), vec![4, 2, 1]);
+
+        // Requesting more blocks than are retained returns all of them.
+        assert_eq!(top_heights(10), vec![4, 2, 1, 3, 5]);
+        assert!(top_heights(0).is_empty());
+    }
+
+    #[async_std::test]
+    async fn test_block_detail_page() {
+        let (public_keys, stake_table) = create_test_stake_table(&[1, 2, 3]);
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+        assert!(data_state.block_detail_page(5).is_none());
+
+        data_state.add_latest_block(create_test_block_detail(5, 0, 2, 64).await);
+        data_state.add_latest_voters(create_test_voters(3, &[0, 2]));
+        data_state.add_latest_voter_stake(VoterStakeSnapshot {
+            height: 5,
+            participating_stake: U256::from(4),
+            total_stake: U256::from(6),
+        });
+
+        let page = data_state.block_detail_page(5).unwrap();
+        assert_eq!(page.block.height, 5);
+        assert_eq!(page.block.num_transactions, 2);
+        assert_eq!(
+            page.voters.unwrap(),
+            vec![
+                NodeIdentity::from_public_key(public_keys[0]),
+                NodeIdentity::from_public_key(public_keys[2]),
+            ]
+        );
+        assert_eq!(page.missing_voters, Some(vec![public_keys[1]]));
+        assert_eq!(page.participating_stake, Some(U256::from(4)));
+
+        assert!(data_state.block_detail_page(6).is_none());
+    }
+
+    #[async_std::test]
+    async fn test_size_participation_correlation() {
+        let (_, stake_table) = create_test_stake_table(&[1, 1, 1, 1]);
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+
+        for height in 1..=3 {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, height * 100).await);
+            data_state.add_latest_voters(create_test_voters(4, &[0, 1, 2]));
+        }
+
+        // Blocks are recorded, but the participation has not varied.
+        assert_eq!(data_state.size_participation_correlation(), None);
+
+        data_state.add_latest_block(create_test_block_detail(4, 0, 0, 400).await);
+        data_state.add_latest_voters(create_test_voters(4, &[0]));
+
+        // The larger blocks saw fewer voters.
+        let correlation = data_state.size_participation_correlation().unwrap();
+        assert!(correlation < 0.0);
+        assert!((correlation + 0.7746).abs() < 0.001);
+    }
+
+    #[async_std::test]
+    async fn test_retention_window() {
+        let mut data_state: DataState = Default::default();
+        data_state.set_retention_window(Some(Duration::from_secs(10)));
+
+        for (height, unix_timestamp) in [(1, 100), (2, 105), (3, 109), (4, 112)] {
+            data_state
+                .add_latest_block(create_test_block_detail(height, unix_timestamp, 0, 0).await);
+        }
+
+        // The first block is more than 10 seconds older than the newest.
+        let heights = |data_state: &DataState| {
+            data_state
+                .latest_blocks()
+                .map(|block| block.height)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(heights(&data_state), vec![2, 3, 4]);
+
+        // A block with an out of order timestamp should not move the window
+        // backwards, nor be evicted ahead of the older blocks.
+        data_state.add_latest_block(create_test_block_detail(5, 101, 0, 0).await);
+        assert_eq!(heights(&data_state), vec![2, 3, 4, 5]);
+
+        data_state.add_latest_block(create_test_block_detail(6, 120, 0, 0).await);
+        assert_eq!(heights(&data_state), vec![4, 5, 6]);
+        data_state.add_latest_block(create_test_block_detail(7, 123, 0, 0).await);
+        assert_eq!(heights(&data_state), vec![6, 7]);
+
+        // Shrinking the window should evict immediately.
+        data_state.add_latest_block(create_test_block_detail(8, 125, 0, 0).await);
+        data_state.set_retention_window(Some(Duration::from_secs(1)));
+        assert_eq!(heights(&data_state), vec![8]);
+    }
+
+    #[async_std::test]
+    async fn test_retention_window_trims_voters() {
+        let mut data_state: DataState = Default::default();
+        let public_key = BLSPubKey::generated_from_seed_indexed([0; 32], 0).0;
+        data_state.add_node_identity(NodeIdentity::from_public_key(public_key));
+        data_state.set_retention_window(Some(Duration::from_secs(10)));
+
+        // Only the first block, which expires, was voted for by the node.
+        for (height, unix_timestamp, voters) in [
+            (1, 100, vec![0]),
+            (2, 105, vec![]),
+            (3, 109, vec![]),
+            (4, 112, vec![]),
+        ] {
+            data_state
+                .add_latest_block(create_test_block_detail(height, unix_timestamp, 0, 0).await);
+            data_state.add_latest_voters(create_test_voters(1, &voters));
+        }
+
+        assert_eq!(data_state.latest_blocks().count(), 3);
+        assert_eq!(data_state.latest_voters().count(), 3);
+        assert_eq!(data_state.participation_rate(&public_key), Some(0.0));
+
+        // Without a retention window, the voters are only bound by their
+        // capacity.
+        data_state.set_retention_window(None);
+        data_state.add_latest_voters(create_test_voters(1, &[0]));
+        assert_eq!(data_state.latest_voters().count(), 4);
+    }
+
+    #[test]
+    fn test_stake_table_updated_at() {
+        let mut data_state: DataState = Default::default();
+        assert_eq!(data_state.stake_table_updated_at(), None);
+
+        let first_update = OffsetDateTime::from_unix_timestamp(100).unwrap();
+        let (_, stake_table) = create_test_stake_table(&[1]);
+        data_state.replace_stake_table_at(stake_table, first_update);
+        assert_eq!(data_state.stake_table_updated_at(), Some(first_update));
+
+        let second_update = OffsetDateTime::from_unix_timestamp(280).unwrap();
+        let (_, stake_table) = create_test_stake_table(&[1, 2]);
+        data_state.replace_stake_table_at(stake_table, second_update);
+        assert_eq!(data_state.stake_table_updated_at(), Some(second_update));
+    }
+
+    #[test]
+    fn test_validator_count() {
+        let (_, stake_table) = create_test_stake_table(&[1, 2, 3]);
+        let data_state = DataState::new(Default::default(), Default::default(), stake_table);
+
+        assert_eq!(data_state.validator_count(SnapshotVersion::Head), 3);
+        assert_eq!(
+            data_state.validator_count(SnapshotVersion::LastEpochStart),
+            3
+        );
+        assert_eq!(data_state.validator_count(SnapshotVersion::BlockNum(0)), 0);
+        assert_eq!(
+            DataState::default().validator_count(SnapshotVersion::Head),
+            0
+        );
+    }
+
+    #[async_std::test]
+    async fn test_approximate_memory_bytes() {
+        let mut data_state: DataState = Default::default();
+        let initial_bytes = data_state.approximate_memory_bytes();
+
+        data_state.add_latest_block(create_test_block_detail(1, 0, 0, 0).await);
+        data_state.add_latest_voters(create_test_voters(16, &[0]));
+        let one_block_bytes = data_state.approximate_memory_bytes();
+        assert!(one_block_bytes > initial_bytes);
+
+        data_state.add_latest_block(create_test_block_detail(2, 0, 0, 0).await);
+        data_state.add_latest_voters(create_test_voters(16, &[0]));
+        assert!(data_state.approximate_memory_bytes() > one_block_bytes);
+    }
+
+    #[async_std::test]
+    async fn test_busiest_block() {
+        let mut data_state: DataState = Default::default();
+        assert!(data_state.busiest_block().is_none());
+
+        for (height, num_transactions) in [(1, 3), (2, 7), (3, 1), (4, 7), (5, 0)] {
+            data_state
+                .add_latest_block(create_test_block_detail(height, 0, num_transactions, 0).await);
+        }
+
+        // Blocks 2 and 4 are tied, so the most recent should be returned.
+        assert_eq!(
+            data_state.busiest_block().map(|block| block.height),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_stake_table_entries_failure() {
+        // A valid, but empty, stake table should not be treated as a
+        // failure.
+        let mut data_state: DataState = Default::default();
+        assert!(data_state
+            .stake_table_entries(SnapshotVersion::LastEpochStart)
+            .is_empty());
+        assert_eq!(data_state.stake_table_iteration_failures(), 0);
+
+        // Block number snapshots are not supported, so the iteration will
+        // fail.
+        let (_, stake_table) = create_test_stake_table(&[1, 2]);
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+        assert_eq!(
+            data_state
+                .stake_table_entries(SnapshotVersion::LastEpochStart)
+                .len(),
+            2
+        );
+        assert!(data_state
+            .stake_table_entries(SnapshotVersion::BlockNum(0))
+            .is_empty());
+        assert_eq!(data_state.stake_table_iteration_failures(), 1);
+    }
+
+    #[async_std::test]
+    async fn test_wait_for_height() {
+        let data_state = Arc::new(RwLock::new(DataState::default()));
+
+        // Nothing is being processed, so this should time out.
+        assert_eq!(
+            super::wait_for_height(data_state.clone(), 2, Duration::from_millis(50)).await,
+            Err(WaitError::Timeout { height: 2 })
+        );
+
+        let wait_handle = async_std::task::spawn(super::wait_for_height(
+            data_state.clone(),
+            2,
+            Duration::from_secs(5),
+        ));
+
+        for height in 1..=2 {
+            data_state
+                .write()
+                .await
+                .add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+        }
+
+        assert_eq!(wait_handle.await, Ok(()));
+    }
+
+    #[test]
+    fn test_stake_table_snapshot_versions() {
+        let mut stake_table = StakeTable::new(3);
+        let public_keys = (0..3)
+            .map(|index| BLSPubKey::generated_from_seed_indexed([0; 32], index).0)
+            .collect::<Vec<_>>();
+        let register = |stake_table: &mut StakeTable<_, _, _>, index: usize| {
+            let state_key = StateKeyPair::generate_from_seed_indexed([0; 32], index as u64);
+            stake_table
+                .register(
+                    public_keys[index],
+                    U256::from(index + 1),
+                    state_key.ver_key(),
+                )
+                .expect("registering stake table entry");
+        };
+
+        register(&mut stake_table, 0);
+        register(&mut stake_table, 1);
+        stake_table.advance();
+        stake_table.advance();
+
+        // Registering a new entry and advancing once places it within the
+        // epoch start snapshot, but not yet the last epoch start snapshot.
+        register(&mut stake_table, 2);
+        stake_table.advance();
+        let new_public_key = public_keys[2];
+
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+        assert_eq!(
+            data_state.voter_attribution_version(),
+            SnapshotVersion::LastEpochStart
+        );
+
+        assert_eq!(
+            data_state.total_stake(SnapshotVersion::EpochStart),
+            Some(U256::from(6))
+        );
+        assert_eq!(
+            data_state.total_stake(SnapshotVersion::LastEpochStart),
+            Some(U256::from(3))
+        );
+        assert_eq!(
+            data_state.stake_of(&new_public_key, SnapshotVersion::EpochStart),
+            Some(U256::from(3))
+        );
+        assert_eq!(
+            data_state.stake_of(&new_public_key, SnapshotVersion::LastEpochStart),
+            None
+        );
+        assert_eq!(
+            data_state.stake_of(&public_keys[0], SnapshotVersion::LastEpochStart),
+            Some(U256::from(1))
+        );
+
+        data_state.set_voter_attribution_version(SnapshotVersion::EpochStart);
+        assert_eq!(
+            data_state.voter_attribution_version(),
+            SnapshotVersion::EpochStart
+        );
+    }
+
+    #[test]
+    fn test_network_summary_total_stake_follows_attribution_version() {
+        let mut stake_table = StakeTable::new(2);
+        let register = |stake_table: &mut StakeTable<_, _, _>, index: u64, stake: u64| {
+            let public_key = BLSPubKey::generated_from_seed_indexed([0; 32], index).0;
+            let state_key = StateKeyPair::generate_from_seed_indexed([0; 32], index);
+            stake_table
+                .register(public_key, U256::from(stake), state_key.ver_key())
+                .expect("registering stake table entry");
+        };
+
+        register(&mut stake_table, 0, 1);
+        stake_table.advance();
+        stake_table.advance();
+
+        // The second entry is only within the epoch start snapshot.
+        register(&mut stake_table, 1, 2);
+        stake_table.advance();
+
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+        assert_eq!(data_state.network_summary().total_stake, U256::from(1));
+
+        data_state.set_voter_attribution_version(SnapshotVersion::EpochStart);
+        assert_eq!(data_state.network_summary().total_stake, U256::from(3));
+    }
+
+    #[test]
+    fn test_record_leaf_annotation_gaps() {
+        let mut data_state: DataState = Default::default();
+        let ingested_at = std::time::Instant::now();
+        let now = ingested_at + Duration::from_millis(5);
+
+        assert_eq!(data_state.record_leaf_annotation(0, ingested_at, now), 0);
+        assert_eq!(data_state.record_leaf_annotation(1, ingested_at, now), 0);
+        assert_eq!(
+            data_state.latest_ingestion_delay(),
+            Some(Duration::from_millis(5))
+        );
+
+        // Heights 2 and 3 were never seen.
+        assert_eq!(data_state.record_leaf_annotation(4, ingested_at, now), 2);
+        assert_eq!(data_state.record_leaf_annotation(5, ingested_at, now), 0);
+
+        // Replayed leaves do not skip any heights.
+        assert_eq!(data_state.record_leaf_annotation(3, ingested_at, now), 0);
+        assert_eq!(data_state.record_leaf_annotation(6, ingested_at, now), 0);
+        assert_eq!(data_state.leaf_height_gaps(), 2);
+    }
+
+    #[async_std::test]
+    async fn test_process_leaf_stream_height_gaps() {
+        let (anomaly_event_sender, mut anomaly_event_receiver) = mpsc::channel(10);
+        let mut data_state: DataState = Default::default();
+        data_state.set_anomaly_event_sender(Some(anomaly_event_sender));
+        let data_state = Arc::new(RwLock::new(data_state));
+        let (block_sender, _block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
+        let (mut leaf_sender, leaf_receiver) = mpsc::channel(10);
+
+        let _process_leaf_stream_task_handle = ProcessLeafStreamTask::new(
+            leaf_receiver,
+            data_state.clone(),
+            block_sender,
+            voters_sender,
+        );
+
+        // The leaves at heights 2 and 3 were dropped before they reached the
+        // leaf stream.
+        for height in [0, 1, 4] {
+            assert_eq!(
+                leaf_sender.send(create_test_leaf(height).await).await,
+                Ok(())
+            );
+        }
+
+        assert_eq!(
+            super::wait_for_height(data_state.clone(), 4, Duration::from_secs(1)).await,
+            Ok(())
+        );
+        assert_eq!(data_state.read().await.leaf_height_gaps(), 2);
+
+        // The test leaves are decided without any voters, which is also
+        // reported for every block after genesis.
+        assert_eq!(
+            anomaly_event_receiver
+                .by_ref()
+                .take(3)
+                .collect::<Vec<_>>()
+                .await,
+            vec![
+                AnomalyEvent::EmptyVoters { height: 1 },
+                AnomalyEvent::LeafHeightGap { from: 2, to: 3 },
+                AnomalyEvent::EmptyVoters { height: 4 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_participation_trend() {
+        let mut data_state: DataState = Default::default();
+        for index in 0..4 {
+            data_state.add_node_identity(NodeIdentity::from_public_key(
+                BLSPubKey::generated_from_seed_indexed([0; 32], index).0,
+            ));
+        }
+
+        for voters in [&[0][..], &[0, 1], &[0, 1, 2], &[0, 1, 2, 3]] {
+            data_state.add_latest_voters(create_test_voters(4, voters));
+        }
+
+        assert_eq!(data_state.participation_trend(2), Some(Trend::Up));
+        assert_eq!(data_state.participation_trend(3), None);
+        assert_eq!(data_state.participation_trend(0), None);
+        assert_eq!(data_state.participation_trend(usize::MAX), None);
+
+        for _ in 0..4 {
+            data_state.add_latest_voters(create_test_voters(4, &[0, 1, 2, 3]));
+        }
+        assert_eq!(data_state.participation_trend(2), Some(Trend::Flat));
+
+        for _ in 0..2 {
+            data_state.add_latest_voters(create_test_voters(4, &[0]));
+        }
+        assert_eq!(data_state.participation_trend(2), Some(Trend::Down));
+    }
+
+    #[test]
+    fn test_add_node_identities() {
+        let mut data_state: DataState = Default::default();
+        let public_keys = (0..4)
+            .map(|index| BLSPubKey::generated_from_seed_indexed([0; 32], index).0)
+            .collect::<Vec<_>>();
+        let named_identity = |index: usize, name: &str| {
+            let mut node_identity = NodeIdentity::from_public_key(public_keys[index]);
+            node_identity.name = Some(name.to_string());
+            node_identity
+        };
+
+        assert_eq!(
+            data_state.add_node_identities(vec![named_identity(0, "a"), named_identity(1, "a")]),
+            (2, 0)
+        );
+
+        // The second batch overlaps with the first, and contains a duplicate
+        // of its own.
+        assert_eq!(
+            data_state.add_node_identities(vec![
+                named_identity(1, "b"),
+                named_identity(2, "b"),
+                named_identity(3, "b"),
+                named_identity(2, "c"),
+            ]),
+            (2, 1)
+        );
+
+        assert_eq!(
+            data_state
+                .node_identity()
+                .map(|node_identity| (*node_identity.public_key(), node_identity.name.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (public_keys[0], Some("a".to_string())),
+                (public_keys[1], Some("b".to_string())),
+                (public_keys[2], Some("c".to_string())),
+                (public_keys[3], Some("b".to_string())),
+            ]
+        );
+    }
+
+    #[async_std::test]
+    async fn test_zero_capacity_is_clamped() {
+        let mut data_state = DataState::new(
+            BoundedBuffer::with_capacity(0),
+            BoundedBuffer::with_capacity(0),
+            Default::default(),
+        );
+        assert_eq!(data_state.latest_blocks_capacity(), 1);
+        assert_eq!(data_state.latest_voters_capacity(), 1);
+
+        data_state.set_latest_blocks_capacity(0);
+        data_state.set_latest_voters_capacity(0);
+        data_state.set_latest_voter_stake_capacity(0);
+        assert_eq!(data_state.latest_blocks_capacity(), 1);
+        assert_eq!(data_state.latest_voters_capacity(), 1);
+        assert_eq!(data_state.latest_voter_stake_capacity(), 1);
+
+        // The most recent block should still be retained, rather than
+        // everything being discarded.
+        data_state.add_latest_block(create_test_block_detail(1, 0, 0, 0).await);
+        data_state.add_latest_block(create_test_block_detail(2, 0, 0, 0).await);
+        assert_eq!(data_state.latest_block_height(), Some(2));
+    }
+
+    #[async_std::test]
+    async fn test_blocks_with_missing_payload() {
+        let mut data_state: DataState = Default::default();
+        data_state.set_latest_blocks_capacity(4);
+
+        for (height, payload_available) in [(1, false), (2, true), (3, false), (4, true)] {
+            data_state.add_latest_block_with_payload_availability(
+                create_test_block_detail(height, 0, 0, 0).await,
+                payload_available,
+            );
+        }
+        assert_eq!(data_state.blocks_with_missing_payload(), 2);
+
+        // Blocks with a missing payload should not count as being empty.
+        assert_eq!(data_state.empty_block_ratio(), Some(1.0));
+
+        // Once the first block has been evicted, it should no longer be
+        // counted.
+        data_state.add_latest_block(create_test_block_detail(5, 0, 1, 0).await);
+        assert_eq!(data_state.blocks_with_missing_payload(), 1);
+        assert_eq!(data_state.empty_block_ratio(), Some(2.0 / 3.0));
+    }
+
+    #[async_std::test]
+    async fn test_blocks_with_missing_payload_out_of_order() {
+        let mut data_state: DataState = Default::default();
+        data_state.add_latest_block_with_payload_availability(
+            create_test_block_detail(3, 0, 0, 0).await,
+            true,
+        );
+
+        // A block that is retained after a higher block should still be
+        // tracked as missing its payload.
+        data_state.add_latest_block_with_payload_availability(
+            create_test_block_detail(1, 0, 0, 0).await,
+            false,
+        );
+        assert_eq!(data_state.blocks_with_missing_payload(), 1);
+    }
+
+    #[async_std::test]
+    async fn test_payload_fallback_count() {
+        let mut data_state = DataState::default();
+        data_state.blocklist_height(4);
+        let data_state = Arc::new(RwLock::new(data_state));
+        let (block_sender, _block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
+
+        // The replayed leaf at height 3, and the blocklisted leaf at height
+        // 4, are skipped, and should not be counted.
+        for (height, payload_available) in [
+            (0, true),
+            (1, false),
+            (2, true),
+            (3, false),
+            (3, false),
+            (4, false),
+        ] {
+            let mut leaf = create_test_leaf(height).await;
+            if !payload_available {
+                leaf.unfill_block_payload();
+            }
+
+            assert!(super::process_incoming_leaf(
+                leaf,
+                OffsetDateTime::now_utc(),
+                data_state.clone(),
+                block_sender.clone(),
+                &mut vec![voters_sender.clone()],
+                None::<mpsc::Sender<VoterStakeSnapshot>>,
+            )
+            .await
+            .is_ok());
+        }
+
+        let mut data_state = data_state.write().await;
+        assert_eq!(data_state.payload_fallback_count(), 2);
+
+        // The counter should not be bound by the retained window.
+        data_state.set_latest_blocks_capacity(1);
+        assert_eq!(data_state.blocks_with_missing_payload(), 1);
+        assert_eq!(data_state.payload_fallback_count(), 2);
+    }
+
+    #[test]
+    fn test_validators_by_stake() {
+        let (public_keys, stake_table) = create_test_stake_table(&[5, 10, 5, 1]);
+        let data_state = DataState::new(Default::default(), Default::default(), stake_table);
+
+        let validators = data_state.validators_by_stake(SnapshotVersion::Head);
+        let stakes = validators
+            .iter()
+            .map(|(_, stake)| stake.as_u64())
+            .collect::<Vec<_>>();
+        assert_eq!(stakes, vec![10, 5, 5, 1]);
+        assert_eq!(validators[0].0, public_keys[1]);
+        assert_eq!(validators[3].0, public_keys[3]);
+
+        // The tied entries should be ordered by the bytes of their keys.
+        let mut tied = vec![public_keys[0], public_keys[2]];
+        tied.sort_by_key(super::canonical_bytes);
+        assert_eq!(vec![validators[1].0, validators[2].0], tied);
+
+        assert!(data_state
+            .validators_by_stake(SnapshotVersion::BlockNum(0))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_check_voters_against_stake_table() {
+        let mut data_state: DataState = Default::default();
+        let subscriber = LevelCapturingSubscriber::default();
+        let levels = subscriber.levels.clone();
+
+        tracing::subscriber::with_default(subscriber, || {
+            // Voters within the stake table should not be reported.
+            assert_eq!(
+                data_state.check_voters_against_stake_table(&create_test_voters(3, &[0, 2]), 3),
+                0
+            );
+            assert!(levels.lock().unwrap().is_empty());
+
+            assert_eq!(
+                data_state.check_voters_against_stake_table(&create_test_voters(5, &[0, 3, 4]), 3),
+                2
+            );
+        });
+
+        assert!(levels.lock().unwrap().contains(&tracing::Level::WARN));
+        assert_eq!(data_state.voters_beyond_stake_table(), 2);
+    }
+
+    #[async_std::test]
+    async fn test_running_aggregates_match_batch() {
+        let smoothing = 0.25;
+        let mut running_aggregates = super::RunningAggregates::new(smoothing);
+        let blocks = [(0, 4, 100), (2, 10, 300), (4, 2, 50), (5, 8, 900)];
+
+        for (unix_timestamp, num_transactions, size) in blocks {
+            running_aggregates.update(
+                &create_test_block_detail(0, unix_timestamp, num_transactions, size).await,
+                Some(num_transactions as f64 / 10.0),
+            );
+        }
+
+        // Compute the same exponential moving averages over the whole batch.
+        let batch_average = |samples: &[f64]| {
+            samples[1..].iter().fold(samples[0], |average, sample| {
+                average + smoothing * (sample - average)
+            })
+        };
+        let sizes = blocks.map(|(_, _, size)| size as f64);
+        let tps = [10.0 / 2.0, 2.0 / 2.0, 8.0 / 1.0];
+        let participation = blocks.map(|(_, num_transactions, _)| num_transactions as f64 / 10.0);
+
+        assert_eq!(running_aggregates.blocks(), 4);
+        assert_eq!(
+            running_aggregates.average_block_size(),
+            Some(batch_average(&sizes))
+        );
+        assert_eq!(running_aggregates.average_tps(), Some(batch_average(&tps)));
+        assert_eq!(
+            running_aggregates.average_participation(),
+            Some(batch_average(&participation))
+        );
+    }
+
+    #[test]
+    fn test_stake_fraction() {
+        assert_eq!(super::stake_fraction(U256::from(1), U256::zero()), None);
+        assert_eq!(
+            super::stake_fraction(U256::from(1), U256::from(4)),
+            Some(0.25)
+        );
+    }
+
+    #[async_std::test]
+    async fn test_missing_voters() {
+        let (public_keys, stake_table) = create_test_stake_table(&[1, 1, 1, 1]);
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+
+        data_state.add_latest_block(create_test_block_detail(1, 0, 0, 0).await);
+        data_state.add_latest_voters(create_test_voters(4, &[0, 2]));
+
+        assert_eq!(
+            data_state.missing_voters(1),
+            Some(vec![public_keys[1], public_keys[3]])
+        );
+        assert_eq!(data_state.missing_voters(2), None);
+    }
+
+    #[async_std::test]
+    async fn test_process_incoming_leaf_without_voter_tracking() {
+        let (_, stake_table) = create_test_stake_table(&[1, 2, 3]);
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+        data_state.set_voter_tracking_enabled(false);
+        let data_state = Arc::new(RwLock::new(data_state));
+        let (block_sender, mut block_receiver) = mpsc::channel(10);
+
+        // The voters receivers are not needed, so they are dropped.
+        let (voters_sender, voters_receiver) = mpsc::channel(10);
+        let (voter_stake_sender, voter_stake_receiver) = mpsc::channel(10);
+        drop(voters_receiver);
+        drop(voter_stake_receiver);
+
+        for height in 1..=3 {
+            assert!(super::process_incoming_leaf(
+                create_test_leaf(height).await,
+                OffsetDateTime::now_utc(),
+                data_state.clone(),
+                block_sender.clone(),
+                &mut vec![voters_sender.clone()],
+                Some(voter_stake_sender.clone()),
+            )
+            .await
+            .is_ok());
+        }
+
+        drop(block_sender);
+        assert_eq!(block_receiver.by_ref().count().await, 3);
+
+        let data_state = data_state.read().await;
+        assert_eq!(data_state.latest_blocks().count(), 3);
+        assert_eq!(data_state.latest_voters().count(), 0);
+        assert_eq!(data_state.latest_voter_stake().count(), 0);
+        assert_eq!(data_state.voters_for_height(3), None);
+        assert_eq!(
+            data_state.running_aggregates().average_participation(),
+            None
+        );
+    }
+
+    #[async_std::test]
+    async fn test_process_incoming_leaf_toggle_voter_tracking() {
+        let data_state = Arc::new(RwLock::new(DataState::default()));
+        let (block_sender, _block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
+
+        for (height, voter_tracking_enabled) in [(1, true), (2, true), (3, false), (4, true)] {
+            data_state
+                .write()
+                .await
+                .set_voter_tracking_enabled(voter_tracking_enabled);
+            assert!(super::process_incoming_leaf(
+                create_test_leaf(height).await,
+                OffsetDateTime::now_utc(),
+                data_state.clone(),
+                block_sender.clone(),
+                &mut vec![voters_sender.clone()],
+                None::<mpsc::Sender<VoterStakeSnapshot>>,
+            )
+            .await
+            .is_ok());
+        }
+
+        // The voters of the blocks that were processed before voter tracking
+        // was disabled have been discarded, so that they are not paired with
+        // the more recent blocks.
+        let data_state = data_state.read().await;
+        assert_eq!(data_state.latest_blocks().count(), 4);
+        assert_eq!(data_state.latest_voters().count(), 1);
+        assert!(data_state.voters_for_height(4).is_some());
+        assert_eq!(data_state.voters_for_height(3), None);
+        assert_eq!(data_state.voters_for_height(2), None);
+        assert_eq!(data_state.voters_for_height(1), None);
+    }
+
+    #[async_std::test]
+    async fn test_latest_fee_recipient() {
+        let mut data_state: DataState = Default::default();
+        assert_eq!(data_state.latest_fee_recipient(), None);
+
+        for (height, fee_recipient) in [(1, 7), (2, 3)] {
+            let mut block = create_test_block_detail(height, 0, 0, 0).await;
+            block.fee_recipient = vec![create_test_fee_account(fee_recipient)];
+            data_state.add_latest_block(block);
+        }
+
+        assert_eq!(
+            data_state.latest_fee_recipient(),
+            Some(create_test_fee_account(3))
+        );
+    }
+
+    #[test]
+    fn test_voter_count_histogram() {
+        let mut data_state: DataState = Default::default();
+        for index in 0..4 {
+            data_state.add_node_identity(NodeIdentity::from_public_key(
+                BLSPubKey::generated_from_seed_indexed([0; 32], index).0,
+            ));
+        }
+
+        for voters in [&[0, 1][..], &[2, 3], &[0, 1, 2, 3], &[], &[1, 3]] {
+            data_state.add_latest_voters(create_test_voters(4, voters));
+        }
+
+        assert_eq!(
+            data_state.voter_count_histogram(),
+            [(0, 1), (2, 3), (4, 1)].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn test_reindex_voters() {
+        let public_keys = (0..3)
+            .map(|index| BLSPubKey::generated_from_seed_indexed([0; 32], index).0)
+            .collect::<Vec<_>>();
+
+        let mut data_state: DataState = Default::default();
+        data_state.add_node_identities(
+            public_keys
+                .iter()
+                .map(|public_key| NodeIdentity::from_public_key(*public_key))
+                .collect(),
+        );
+        data_state.add_latest_voters(create_test_voters(3, &[0]));
+        data_state.add_latest_voters(create_test_voters(3, &[1, 2]));
+
+        // Reverse the order of the identities.
+        let old_order = data_state
+            .node_identity()
+            .map(|node_identity| *node_identity.public_key())
+            .collect::<Vec<_>>();
+        let mut reversed_identities = data_state.node_identity().cloned().collect::<Vec<_>>();
+        reversed_identities.reverse();
+        data_state.node_identity = reversed_identities;
+        data_state.reindex_voters(&old_order);
+
+        assert_eq!(
+            data_state.voting_pattern(&public_keys[0], 2),
+            Some(vec![true, false])
+        );
+        assert_eq!(
+            data_state.voting_pattern(&public_keys[1], 2),
+            Some(vec![false, true])
+        );
+        assert_eq!(
+            data_state.latest_voters().cloned().collect::<Vec<_>>(),
+            vec![create_test_voters(3, &[2]), create_test_voters(3, &[0, 1])]
+        );
+    }
+
+    #[test]
+    fn test_genesis_warnings_suppressed() {
+        let data_state = Arc::new(RwLock::new(DataState::default()));
+        let (block_sender, _block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
+        let process_leaf = |height: u64| {
+            let data_state = data_state.clone();
+            let block_sender = block_sender.clone();
+            let voters_sender = voters_sender.clone();
+            async_std::task::block_on(async move {
+                super::process_incoming_leaf(
+                    create_test_leaf(height).await,
+                    OffsetDateTime::now_utc(),
+                    data_state,
+                    block_sender,
+                    &mut vec![voters_sender],
+                    None::<mpsc::Sender<VoterStakeSnapshot>>,
+                )
+                .await
+            })
+        };
+
+        // The genesis leaf has no voters, but should not be warned about.
+        let subscriber = LevelCapturingSubscriber::default();
+        let levels = subscriber.levels.clone();
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(process_leaf(0).is_ok());
+        });
+        assert!(!levels.lock().unwrap().contains(&tracing::Level::WARN));
+        assert!(async_std::task::block_on(data_state.read()).genesis_handled());
+
+        // A later leaf without voters is an anomaly.
+        let subscriber = LevelCapturingSubscriber::default();
+        let levels = subscriber.levels.clone();
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(process_leaf(1).is_ok());
+        });
+        assert!(levels.lock().unwrap().contains(&tracing::Level::WARN));
+    }
+
+    #[test]
+    fn test_node_profile() {
+        let (public_keys, stake_table) = create_test_stake_table(&[5, 7]);
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+
+        for voters in [&[0, 1][..], &[1], &[0, 1], &[1], &[1]] {
+            data_state.add_latest_voters(create_test_voters(2, voters));
+        }
+
+        let profile = data_state.node_profile(&public_keys[0]).unwrap();
+        assert_eq!(profile.identity.public_key(), &public_keys[0]);
+        assert_eq!(profile.stake, Some(U256::from(5)));
+        assert_eq!(profile.participation_rate, Some(0.4));
+        assert_eq!(profile.absence_streak, 2);
+        assert_eq!(
+            profile.recent_voting_pattern,
+            vec![true, false, true, false, false]
+        );
+
+        let profile = data_state.node_profile(&public_keys[1]).unwrap();
+        assert_eq!(profile.participation_rate, Some(1.0));
+        assert_eq!(profile.absence_streak, 0);
+
+        let unknown_public_key = BLSPubKey::generated_from_seed_indexed([0; 32], 2).0;
+        assert_eq!(data_state.node_profile(&unknown_public_key), None);
+    }
+
+    #[test]
+    fn test_compute_voters_bitvec_length_matches_node_identity() {
+        let public_keys = (0..40)
+            .map(|index| BLSPubKey::generated_from_seed_indexed([0; 32], index).0)
+            .collect::<Vec<_>>();
+        let voters_set = public_keys
+            .iter()
+            .step_by(3)
+            .copied()
+            .collect::<std::collections::HashSet<_>>();
+
+        // The lengths are chosen to not be multiples of the u16 storage.
+        for len in [1, 5, 16, 17, 33, 40] {
+            let node_identity = public_keys[..len]
+                .iter()
+                .map(|public_key| NodeIdentity::from_public_key(*public_key))
+                .collect::<Vec<_>>();
+
+            let voters = super::compute_voters_bitvec(
+                &node_identity,
+                &voters_set,
+                create_test_voters(len, &[]),
+            );
+            assert_eq!(voters.len(), node_identity.len());
+            assert_eq!(voters.count_ones(), (len + 2) / 3);
+        }
+    }
+
+    #[test]
+    fn test_stake_weighted_uptime() {
+        let (public_keys, stake_table) = create_test_stake_table(&[1, 3]);
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+        assert_eq!(data_state.network_stake_weighted_uptime(), None);
+
+        for voters in [&[0, 1][..], &[1], &[1], &[1]] {
+            data_state.add_latest_voters(create_test_voters(2, voters));
+        }
+
+        // The uptime of a single node is not affected by its own stake.
+        assert_eq!(data_state.participation_rate(&public_keys[0]), Some(0.25));
+        assert_eq!(
+            data_state.stake_weighted_uptime(&public_keys[0], SnapshotVersion::Head),
+            Some(0.25)
+        );
+        assert_eq!(
+            data_state.stake_weighted_uptime(&public_keys[1], SnapshotVersion::Head),
+            Some(1.0)
+        );
+        assert_eq!(
+            data_state.stake_weighted_uptime(&public_keys[1], SnapshotVersion::BlockNum(0)),
+            None
+        );
+
+        // The aggregate is weighted by stake: (1 * 0.25 + 3 * 1.0) / 4.
+        assert_eq!(data_state.network_stake_weighted_uptime(), Some(0.8125));
+    }
+
+    #[test]
+    fn test_voter_stake_entropy() {
+        let entropy = |stakes: &[u64], voters: &[usize]| {
+            let (_, stake_table) = create_test_stake_table(stakes);
+            let mut data_state =
+                DataState::new(Default::default(), Default::default(), stake_table);
+            data_state.add_latest_voters(create_test_voters(stakes.len(), voters));
+            data_state.voter_stake_entropy(SnapshotVersion::Head)
+        };
+
+        let even = entropy(&[1, 1, 1, 1], &[0, 1, 2, 3]).unwrap();
+        assert!((even - 2.0).abs() < 1e-9);
+
+        // Only the stake of the voters is considered.
+        let even_voters = entropy(&[1, 1, 1, 100], &[0, 1, 2]).unwrap();
+        assert!((even_voters - 3f64.log2()).abs() < 1e-9);
+
+        let skewed = entropy(&[97, 1, 1, 1], &[0, 1, 2, 3]).unwrap();
+        assert!(skewed < even);
+
+        assert_eq!(entropy(&[1, 1], &[]), None);
+        assert_eq!(
+            DataState::default().voter_stake_entropy(SnapshotVersion::Head),
+            None
+        );
+    }
+
+    #[test]
+    fn test_silent_stake_fraction() {
+        let (_, stake_table) = create_test_stake_table(&[6, 1, 1]);
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+        assert_eq!(data_state.silent_stake_fraction(), None);
+
+        for voters in [&[1][..], &[1, 2], &[2]] {
+            data_state.add_latest_voters(create_test_voters(3, voters));
+        }
+
+        // The validator with the most stake never voted.
+        assert_eq!(data_state.silent_stake_fraction(), Some(0.75));
+
+        data_state.add_latest_voters(create_test_voters(3, &[0]));
+        assert_eq!(data_state.silent_stake_fraction(), Some(0.0));
+    }
+
+    #[async_std::test]
+    async fn test_summary_stream() {
+        let data_state = Arc::new(RwLock::new(DataState::default()));
+        let interval = Duration::from_millis(20);
+        let mut summary_stream = Box::pin(super::summary_stream(data_state.clone(), interval));
+
+        let start = std::time::Instant::now();
+        let summary = summary_stream.next().await.unwrap();
+        assert!(start.elapsed() >= interval);
+        assert_eq!(summary.latest_block_height, None);
+
+        data_state
+            .write()
+            .await
+            .add_latest_block(create_test_block_detail(1, 0, 0, 0).await);
+
+        let start = std::time::Instant::now();
+        let summary = summary_stream.next().await.unwrap();
+        assert!(start.elapsed() >= interval);
+        assert_eq!(summary.latest_block_height, Some(1));
+    }
+
+    #[async_std::test]
+    async fn test_block_and_voters_at() {
+        let mut data_state: DataState = Default::default();
+        data_state.set_latest_voters_capacity(2);
+
+        for height in 0..3 {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+            data_state.add_latest_voters(create_test_voters(3, &[height as usize]));
+        }
+
+        assert_eq!(data_state.block_at(0).map(|block| block.height), Some(0));
+        assert_eq!(data_state.block_at(2).map(|block| block.height), Some(2));
+        assert!(data_state.block_at(3).is_none());
+
+        // The voters for the oldest block have been evicted.
+        assert_eq!(data_state.voters_at(0), None);
+        assert_eq!(data_state.voters_at(1), Some(&create_test_voters(3, &[1])));
+        assert_eq!(data_state.voters_at(2), Some(&create_test_voters(3, &[2])));
+        assert_eq!(data_state.voters_at(3), None);
+    }
+
+    #[async_std::test]
+    async fn test_distinct_fee_recipients() {
+        let mut data_state: DataState = Default::default();
+        assert_eq!(data_state.distinct_fee_recipients(), 0);
+
+        for (height, fee_recipients) in [(1, &[1, 2][..]), (2, &[2]), (3, &[3, 1]), (4, &[])] {
+            let mut block = create_test_block_detail(height, 0, 0, 0).await;
+            block.fee_recipient = fee_recipients
+                .iter()
+                .map(|index| create_test_fee_account(*index))
+                .collect();
+            data_state.add_latest_block(block);
+        }
+
+        assert_eq!(data_state.distinct_fee_recipients(), 3);
+    }
+
+    #[async_std::test]
+    async fn test_process_incoming_leaf_blocklisted() {
+        let data_state = Arc::new(RwLock::new(DataState::default()));
+        let (block_sender, mut block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
+
+        let blocklisted_by_hash = create_test_leaf(3).await;
+        {
+            let mut data_state = data_state.write().await;
+            data_state.blocklist_height(2);
+            data_state.blocklist_hash(create_block_detail_from_leaf(&blocklisted_by_hash).hash);
+        }
+
+        for leaf in [
+            create_test_leaf(1).await,
+            create_test_leaf(2).await,
+            blocklisted_by_hash,
+            create_test_leaf(4).await,
+        ] {
+            assert!(super::process_incoming_leaf(
+                leaf,
+                OffsetDateTime::now_utc(),
+                data_state.clone(),
+                block_sender.clone(),
+                &mut vec![voters_sender.clone()],
+                None::<mpsc::Sender<VoterStakeSnapshot>>,
+            )
+            .await
+            .is_ok());
+        }
+
+        drop(block_sender);
+        assert_eq!(
+            block_receiver
+                .map(|block| block.height)
+                .collect::<Vec<_>>()
+                .await,
+            vec![1, 4]
+        );
+
+        let data_state = data_state.read().await;
+        assert_eq!(
+            data_state
+                .latest_blocks()
+                .map(|block| block.height)
+                .collect::<Vec<_>>(),
+            vec![1, 4]
+        );
+        assert_eq!(data_state.cumulative_blocks(), 2);
+    }
+
+    #[async_std::test]
+    async fn test_header_summary_from_block_detail() {
+        let block = create_test_block_detail(7, 1_700_000_000, 3, 128).await;
+        let summary = HeaderSummary::from(&block);
+
+        assert_eq!(summary.height, 7);
+        assert_eq!(summary.hash, block.hash);
+        assert_eq!(summary.time.0.unix_timestamp(), 1_700_000_000);
+        assert_eq!(summary.proposer_id, block.proposer_id);
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert!(json.get("height").is_some());
+        assert!(json.get("block_reward").is_none());
+        assert!(json.get("size").is_none());
+
+        let summary = block.to_header_summary();
+        assert_eq!(summary.height, 7);
+        assert_eq!(summary.hash, block.hash);
+        assert_eq!(summary.proposer_id, block.proposer_id);
+    }
+
+    #[async_std::test]
+    async fn test_missing_heights() {
+        let mut data_state = DataState::default();
+        for height in [3, 4, 6, 7] {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+        }
+
+        // Heights outside of the retained window are not reported, and
+        // duplicates are only reported once.
+        assert_eq!(
+            data_state.missing_heights(&[1, 2, 3, 4, 5, 5, 6, 7, 8]),
+            vec![5]
+        );
+        assert_eq!(data_state.missing_heights(&[3, 4, 6, 7]), Vec::<u64>::new());
+        assert_eq!(
+            DataState::default().missing_heights(&[1, 2, 3]),
+            Vec::<u64>::new()
+        );
+    }
+
+    #[async_std::test]
+    async fn test_blocks_with_gaps() {
+        let mut data_state: DataState = Default::default();
+        assert!(data_state.blocks_with_gaps().is_empty());
+
+        for height in [1, 2, 5, 6, 8] {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+        }
+
+        let entries = data_state
+            .blocks_with_gaps()
+            .into_iter()
+            .map(|entry| match entry {
+                BlockOrGap::Block(block) => format!("{}", block.height),
+                BlockOrGap::Gap { from, to } => format!("{}..={}", from, to),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(entries, vec!["1", "2", "3..=4", "5", "6", "7..=7", "8"]);
+
+        // Blocks at the maximum height must not overflow the gap detection.
+        let mut data_state: DataState = Default::default();
+        for height in [u64::MAX - 2, u64::MAX, u64::MAX] {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+        }
+
+        let gaps = data_state
+            .blocks_with_gaps()
+            .into_iter()
+            .filter_map(|entry| match entry {
+                BlockOrGap::Block(_) => None,
+                BlockOrGap::Gap { from, to } => Some((from, to)),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(gaps, vec![(u64::MAX - 1, u64::MAX - 1)]);
+    }
+
+    #[async_std::test]
+    async fn test_process_incoming_leaf_finalization_lag() {
+        let data_state = Arc::new(RwLock::new(DataState::default()));
+        let (block_sender, _block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
+
+        let mut leaf = create_test_leaf(1).await;
+        *leaf.block_header_mut().timestamp_mut() = 996;
+
+        // The lag is measured against the time at which the leaf was
+        // received, rather than the time at which it is processed.
+        assert!(super::process_incoming_leaf(
+            leaf,
+            OffsetDateTime::from_unix_timestamp(1_000).unwrap(),
+            data_state.clone(),
+            block_sender,
+            &mut vec![voters_sender],
+            None::<mpsc::Sender<VoterStakeSnapshot>>,
+        )
+        .await
+        .is_ok());
+
+        assert_eq!(
+            data_state.read().await.average_finalization_lag(),
+            Some(Duration::from_secs(4))
+        );
+    }
+
+    #[async_std::test]
+    async fn test_average_finalization_lag() {
+        let mut data_state = DataState::default();
+        assert_eq!(data_state.average_finalization_lag(), None);
+
+        let observed_at = OffsetDateTime::from_unix_timestamp(1_000).unwrap();
+        assert_eq!(
+            data_state.record_finalization_lag(
+                &create_test_block_detail(1, 996, 0, 0).await,
+                observed_at
+            ),
+            Duration::from_secs(4)
+        );
+        assert_eq!(
+            data_state.record_finalization_lag(
+                &create_test_block_detail(2, 998, 0, 0).await,
+                observed_at
+            ),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            data_state.average_finalization_lag(),
+            Some(Duration::from_secs(3))
+        );
+
+        // A block from the future, due to clock skew, is clamped to zero.
+        assert_eq!(
+            data_state.record_finalization_lag(
+                &create_test_block_detail(3, 1_005, 0, 0).await,
+                observed_at
+            ),
+            Duration::ZERO
+        );
+        assert_eq!(
+            data_state.average_finalization_lag(),
+            Some(Duration::from_secs(2))
+        );
+    }
 
-        // Attempt to receive, and we should get an error.
-        let receive_result = sender.send(1).await;
+    #[test]
+    fn test_group_participation_rate() {
+        let mut data_state: DataState = Default::default();
+        let public_keys = (0..4)
+            .map(|index| BLSPubKey::generated_from_seed_indexed([0; 32], index).0)
+            .collect::<Vec<_>>();
+        for public_key in public_keys.iter().take(3) {
+            data_state.add_node_identity(NodeIdentity::from_public_key(*public_key));
+        }
 
-        assert!(receive_result.is_err());
-        let err = receive_result.unwrap_err();
+        data_state.add_latest_voters(create_test_voters(3, &[0, 1]));
+        data_state.add_latest_voters(create_test_voters(3, &[0, 2]));
+        data_state.add_latest_voters(create_test_voters(3, &[1, 2]));
+        data_state.add_latest_voters(create_test_voters(3, &[0]));
 
-        let process_leaf_err = super::ProcessLeafError::BlockSendError(err);
+        // The first node voted 3 of 4 times, and the second 2 of 4 times.
+        assert_eq!(
+            data_state.group_participation_rate(&public_keys[0..2]),
+            Some(5.0 / 8.0)
+        );
 
+        // The unknown fourth node is ignored.
         assert_eq!(
-            format!("{:?}", process_leaf_err),
-            "BlockSendError(SendError { kind: Disconnected })"
+            data_state.group_participation_rate(&[public_keys[2], public_keys[3]]),
+            Some(2.0 / 4.0)
         );
+        assert_eq!(data_state.group_participation_rate(&public_keys[3..]), None);
     }
 
     #[async_std::test]
-    async fn test_process_leaf_stream() {
-        let data_state: DataState = Default::default();
-        let data_state = Arc::new(RwLock::new(data_state));
-        let (block_sender, block_receiver) = futures::channel::mpsc::channel(1);
-        let (voters_sender, voters_receiver) = futures::channel::mpsc::channel(1);
-        let (leaf_sender, leaf_receiver) = futures::channel::mpsc::channel(1);
+    async fn test_process_node_identity_stream_resolves_location() {
+        let data_state = Arc::new(RwLock::new(DataState::default()));
+        let (mut node_identity_sender_1, node_identity_receiver_1) =
+            futures::channel::mpsc::channel(1);
+        let (node_identity_sender_2, mut node_identity_receiver_2) =
+            futures::channel::mpsc::channel(1);
 
-        let mut process_leaf_stream_task_handle = ProcessLeafStreamTask::new(
-            leaf_receiver,
-            data_state.clone(),
-            block_sender,
-            voters_sender,
-        );
+        let mut process_node_identity_task_handle =
+            ProcessNodeIdentityStreamTask::new_with_geo_resolver(
+                node_identity_receiver_1,
+                data_state.clone(),
+                node_identity_sender_2,
+                Arc::new(MockGeoResolver::default()),
+            );
+
+        let public_key = BLSPubKey::generated_from_seed_indexed([0; 32], 0).0;
+        let mut node_identity = NodeIdentity::from_public_key(public_key);
+        node_identity.public_url = Some(Url::parse("http://192.0.2.1:8080/").unwrap());
+
+        assert_eq!(node_identity_sender_1.send(node_identity).await, Ok(()));
+
+        let expected_location =
+            LocationDetails::new(Some((40.7128, -74.0060)), Some("US".to_string()));
+        let received = node_identity_receiver_2.next().await.unwrap();
+        assert_eq!(received.location(), Some(&expected_location));
 
         {
             let data_state = data_state.read().await;
-            // Latest blocks should be empty
-            assert_eq!(data_state.latest_blocks().count(), 0);
-            // Latest voters should be empty
-            assert_eq!(data_state.latest_voters().count(), 0);
+            assert_eq!(
+                data_state
+                    .node_identity()
+                    .next()
+                    .and_then(NodeIdentity::location),
+                Some(&expected_location)
+            );
         }
 
-        let validated_state = ValidatedState {
-            block_merkle_tree: BlockMerkleTree::new(32),
-            fee_merkle_tree: FeeMerkleTree::new(32),
-            chain_config: ChainConfig::default().into(),
-        };
-        let instance_state = NodeState::mock();
+        if let Some(process_node_identity_task_handle) =
+            process_node_identity_task_handle.task_handle.take()
+        {
+            assert_eq!(process_node_identity_task_handle.cancel().await, None);
+        }
+    }
 
-        let sample_leaf = Leaf::genesis(&validated_state, &instance_state).await;
+    #[async_std::test]
+    async fn test_poisoned_locks() {
+        let data_state = Arc::new(RwLock::new(DataState::default()));
+        data_state
+            .write()
+            .await
+            .add_latest_block(create_test_block_detail(1, 0, 0, 0).await);
 
-        let mut leaf_sender = leaf_sender;
-        // We should be able to send a leaf without issue
-        assert_eq!(leaf_sender.send(sample_leaf).await, Ok(()),);
+        // A panic while holding the write lock should not prevent the
+        // subsequent readers from acquiring it.
+        let panicking_data_state = data_state.clone();
+        let panic_result = std::thread::spawn(move || {
+            async_std::task::block_on(async move {
+                let _guard = panicking_data_state.write().await;
+                panic!("panicking while holding the data state write lock");
+            })
+        })
+        .join();
+        assert!(panic_result.is_err());
+        assert_eq!(data_state.read().await.latest_block_height(), Some(1));
 
-        let mut block_receiver = block_receiver;
-        // We should receive a Block Detail.
+        // A panic while holding the network summary cache should be
+        // recovered from, and logged.
+        let mut data_state = data_state.write().await;
+        let network_summary_cache = &data_state.network_summary_cache;
+        let panic_result = std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _guard = network_summary_cache.lock();
+                    panic!("panicking while holding the network summary cache");
+                })
+                .join()
+        });
+        assert!(panic_result.is_err());
+        assert!(data_state.network_summary_cache.is_poisoned());
 
-        let next_block = block_receiver.next().await;
-        assert!(next_block.is_some());
+        let subscriber = LevelCapturingSubscriber::default();
+        let levels = subscriber.levels.clone();
+        let network_summary =
+            tracing::subscriber::with_default(subscriber, || data_state.network_summary());
+        assert_eq!(network_summary.latest_block_height, Some(1));
+        assert!(levels.lock().unwrap().contains(&tracing::Level::WARN));
+        assert!(!data_state.network_summary_cache.is_poisoned());
 
-        let mut voters_receiver = voters_receiver;
-        // We should receive a BitVec of voters.
-        let next_voters = voters_receiver.next().await;
-        assert!(next_voters.is_some());
+        data_state.add_latest_block(create_test_block_detail(2, 0, 0, 0).await);
+        assert_eq!(data_state.network_summary().latest_block_height, Some(2));
+    }
 
-        {
-            let data_state = data_state.read().await;
-            // Latest blocks should now have a single entry
-            assert_eq!(data_state.latest_blocks().count(), 1);
-            // Latest voters should now have a single entry
-            assert_eq!(data_state.latest_voters().count(), 1);
+    #[async_std::test]
+    async fn test_network_summary_cache() {
+        let mut data_state = DataState::default();
+        data_state.add_latest_block(create_test_block_detail(1, 0, 0, 0).await);
+        assert_eq!(data_state.network_summary().known_node_count, 0);
+
+        // Bypassing the mutators leaves the cached summary in place, which
+        // demonstrates that it is reused at the same height.
+        let public_key = BLSPubKey::generated_from_seed_indexed([0; 32], 0).0;
+        data_state
+            .node_identity
+            .push(NodeIdentity::from_public_key(public_key));
+        assert_eq!(data_state.network_summary().known_node_count, 0);
+
+        // Processing a new block invalidates the cached summary.
+        data_state.add_latest_block(create_test_block_detail(2, 0, 0, 0).await);
+        let summary = data_state.network_summary();
+        assert_eq!(summary.latest_block_height, Some(2));
+        assert_eq!(summary.known_node_count, 1);
+    }
+
+    #[async_std::test]
+    async fn test_below_quorum_blocks() {
+        let mut data_state = DataState::default();
+        for (height, participating_stake) in [(1, 90), (2, 50), (3, 67), (4, 0)] {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+            data_state.add_latest_voter_stake(VoterStakeSnapshot {
+                height,
+                participating_stake: U256::from(participating_stake),
+                total_stake: U256::from(100),
+            });
         }
 
-        // We explicitly drop these, as it should make the task clean up.
-        drop(block_receiver);
-        drop(leaf_sender);
+        // A block with no total stake cannot be judged.
+        data_state.add_latest_block(create_test_block_detail(5, 0, 0, 0).await);
+        data_state.add_latest_voter_stake(VoterStakeSnapshot {
+            height: 5,
+            participating_stake: U256::zero(),
+            total_stake: U256::zero(),
+        });
+
+        assert_eq!(data_state.below_quorum_blocks(0.67), vec![2, 4]);
+        assert_eq!(data_state.below_quorum_blocks(0.0), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_voters_by_node() {
+        let mut data_state: DataState = Default::default();
+        let public_keys = (0..3)
+            .map(|index| BLSPubKey::generated_from_seed_indexed([0; 32], index).0)
+            .collect::<Vec<_>>();
+        for public_key in public_keys.iter() {
+            data_state.add_node_identity(NodeIdentity::from_public_key(*public_key));
+        }
+
+        data_state.add_latest_voters(create_test_voters(2, &[0, 1]));
+        data_state.add_latest_voters(create_test_voters(3, &[1, 2]));
+        data_state.add_latest_voters(create_test_voters(3, &[0]));
+
+        let voters_by_node = data_state.voters_by_node();
+        assert_eq!(voters_by_node.len(), 3);
+        assert_eq!(voters_by_node[&public_keys[0]], vec![true, false, true]);
+        assert_eq!(voters_by_node[&public_keys[1]], vec![true, true, false]);
+
+        // The shorter voters recorded before the last node was known are
+        // normalized to not having voted.
+        assert_eq!(voters_by_node[&public_keys[2]], vec![false, true, false]);
+
+        for (index, voters) in data_state.normalized_voters().iter().enumerate() {
+            for (node_index, public_key) in public_keys.iter().enumerate() {
+                assert_eq!(voters_by_node[public_key][index], voters[node_index]);
+            }
+        }
+    }
 
+    #[async_std::test]
+    async fn test_count_transactions_with_filter() {
+        let excluded_namespace = NamespaceId::from(2_u32);
+        let transactions = vec![
+            Transaction::new(NamespaceId::from(1_u32), vec![0; 10]),
+            Transaction::new(excluded_namespace, vec![0; 20]),
+            Transaction::new(NamespaceId::from(1_u32), vec![0; 5]),
+        ];
+        let (payload, metadata) =
+            Payload::from_transactions(transactions, &Default::default(), &Default::default())
+                .await
+                .unwrap();
+
+        assert_eq!(count_transactions(&payload, &metadata, |_| true), (3, 35));
         assert_eq!(
-            process_leaf_stream_task_handle
-                .task_handle
-                .take()
-                .unwrap()
-                .timeout(Duration::from_millis(200))
-                .await,
-            Ok(())
+            count_transactions(&payload, &metadata, |tx| tx.namespace()
+                != excluded_namespace),
+            (2, 15)
         );
     }
 
     #[async_std::test]
-    async fn test_process_node_identity_stream() {
-        let data_state: DataState = Default::default();
+    async fn test_process_incoming_leaf_transaction_filter() {
+        let excluded_namespace = NamespaceId::from(2_u32);
+        let transactions = vec![
+            Transaction::new(NamespaceId::from(1_u32), vec![0; 10]),
+            Transaction::new(excluded_namespace, vec![0; 20]),
+            Transaction::new(NamespaceId::from(1_u32), vec![0; 5]),
+        ];
+        let (payload, metadata) =
+            Payload::from_transactions(transactions, &Default::default(), &Default::default())
+                .await
+                .unwrap();
+
+        let mut leaf = create_test_leaf(1).await;
+        *leaf.block_header_mut().ns_table_mut() = metadata;
+        leaf.fill_block_payload_unchecked(payload);
+
+        // By default, every transaction is counted.
+        let block_detail = create_block_detail_from_leaf(&leaf);
+        assert_eq!((block_detail.num_transactions, block_detail.size), (3, 35));
+
+        let mut data_state = DataState::default();
+        data_state.set_transaction_filter(Some(Arc::new(move |tx: &Transaction| {
+            tx.namespace() != excluded_namespace
+        })));
         let data_state = Arc::new(RwLock::new(data_state));
-        let (node_identity_sender_1, node_identity_receiver_1) = futures::channel::mpsc::channel(1);
-        let (node_identity_sender_2, node_identity_receiver_2) = futures::channel::mpsc::channel(1);
+        let (block_sender, mut block_receiver) = mpsc::channel(10);
+        let (voters_sender, _voters_receiver) = mpsc::channel(10);
 
-        let mut process_node_identity_task_handle = ProcessNodeIdentityStreamTask::new(
-            node_identity_receiver_1,
+        assert!(super::process_incoming_leaf(
+            leaf,
+            OffsetDateTime::now_utc(),
             data_state.clone(),
-            node_identity_sender_2,
+            block_sender,
+            &mut vec![voters_sender],
+            None::<mpsc::Sender<VoterStakeSnapshot>>,
+        )
+        .await
+        .is_ok());
+
+        // Both the retained block and the block that is sent exclude the
+        // filtered transactions.
+        let block_detail = block_receiver.next().await.unwrap();
+        assert_eq!((block_detail.num_transactions, block_detail.size), (2, 15));
+        let data_state = data_state.read().await;
+        let block_detail = data_state.latest_block().unwrap();
+        assert_eq!((block_detail.num_transactions, block_detail.size), (2, 15));
+    }
+
+    #[async_std::test]
+    async fn test_voter_churn() {
+        let mut data_state: DataState = Default::default();
+        for index in 0..4 {
+            let public_key = BLSPubKey::generated_from_seed_indexed([0; 32], index).0;
+            data_state.add_node_identity(NodeIdentity::from_public_key(public_key));
+        }
+
+        for (height, voters) in [
+            (1, create_test_voters(3, &[0, 1])),
+            (2, create_test_voters(4, &[1, 2, 3])),
+            (3, create_test_voters(4, &[1, 2, 3])),
+            (4, create_test_voters(4, &[0])),
+        ] {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+            data_state.add_latest_voters(voters);
+        }
+
+        assert_eq!(
+            data_state.voter_churn(),
+            vec![(2, 2, 1), (3, 0, 0), (4, 1, 3)]
         );
+    }
 
-        {
-            let data_state = data_state.read().await;
-            // Latest blocks should be empty
-            assert_eq!(data_state.node_identity().count(), 0);
+    #[async_std::test]
+    async fn test_timestamp_anomalies() {
+        let mut data_state: DataState = Default::default();
+        for (height, unix_timestamp) in [(1, 10), (2, 12), (3, 11), (4, 11), (5, 15), (6, 9)] {
+            data_state
+                .add_latest_block(create_test_block_detail(height, unix_timestamp, 0, 0).await);
         }
 
-        // Send a node update to the Stream
-        let public_key_1 = BLSPubKey::generated_from_seed_indexed([0; 32], 0).0;
-        let node_identity_1 = NodeIdentity::from_public_key(public_key_1);
+        // Equal timestamps are not an anomaly.
+        assert_eq!(data_state.timestamp_anomalies(), vec![(3, 2), (6, 5)]);
+    }
 
-        let mut node_identity_sender_1 = node_identity_sender_1;
-        let mut node_identity_receiver_2 = node_identity_receiver_2;
+    #[async_std::test]
+    async fn test_blocks_sorted_by_height() {
+        let mut data_state: DataState = Default::default();
+        assert!(data_state.blocks_sorted_by_height().is_empty());
+
+        for height in [3, 1, 4, 2, 5] {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, 0).await);
+        }
 
         assert_eq!(
-            node_identity_sender_1.send(node_identity_1.clone()).await,
-            Ok(())
+            data_state
+                .blocks_sorted_by_height()
+                .into_iter()
+                .map(|block| block.height)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
         );
 
+        // The insertion order is left unchanged.
         assert_eq!(
-            node_identity_receiver_2.next().await,
-            Some(node_identity_1.clone())
+            data_state
+                .latest_blocks()
+                .map(|block| block.height)
+                .collect::<Vec<_>>(),
+            vec![3, 1, 4, 2, 5]
         );
+    }
 
-        {
-            let data_state = data_state.read().await;
-            // Latest blocks should now have a single entry
-            assert_eq!(data_state.node_identity().count(), 1);
-            assert_eq!(data_state.node_identity().next(), Some(&node_identity_1));
+    #[async_std::test]
+    async fn test_estimated_time_to_height() {
+        let mut data_state: DataState = Default::default();
+        let now = OffsetDateTime::from_unix_timestamp(19).unwrap();
+        assert_eq!(data_state.estimated_time_to_height(10, now), None);
+
+        // A steady rate of one block every two seconds.
+        for height in 1..=5 {
+            data_state.add_latest_block(
+                create_test_block_detail(height, 8 + 2 * height as i64, 0, 0).await,
+            );
         }
 
-        // If we send the same node identity again, we should not have a new entry.
+        // Block 10 is expected at 28 seconds, 9 seconds from now.
         assert_eq!(
-            node_identity_sender_1.send(node_identity_1.clone()).await,
-            Ok(())
+            data_state.estimated_time_to_height(10, now),
+            Some(Duration::from_secs(9))
         );
+        assert_eq!(data_state.estimated_time_to_height(5, now), None);
 
+        // An overdue height is expected immediately.
+        let later = OffsetDateTime::from_unix_timestamp(100).unwrap();
         assert_eq!(
-            node_identity_receiver_2.next().await,
-            Some(node_identity_1.clone())
+            data_state.estimated_time_to_height(10, later),
+            Some(Duration::ZERO)
         );
 
-        {
-            let data_state = data_state.read().await;
-            // Latest blocks should now have a single entry
-            assert_eq!(data_state.node_identity().count(), 1);
-            assert_eq!(data_state.node_identity().next(), Some(&node_identity_1));
+        // A height that is too far away to be represented has no estimate.
+        assert_eq!(data_state.estimated_time_to_height(u64::MAX, now), None);
+    }
+
+    #[async_std::test]
+    async fn test_block_interval_iqr() {
+        let mut data_state = DataState::default();
+        for (height, unix_timestamp) in [(1, 0), (2, 1), (3, 3), (4, 6)] {
+            data_state
+                .add_latest_block(create_test_block_detail(height, unix_timestamp, 0, 0).await);
         }
 
-        // If we send an update for that node instead, it should update the
-        // entry.
-        let node_identity_1 = NodeIdentity::new(
-            public_key_1,
-            Some("name".to_string()),
-            Some(Url::parse("https://example.com/").unwrap()),
-            Some("company".to_string()),
-            Some(Url::parse("https://example.com/").unwrap()),
-            Some(LocationDetails::new(
-                Some((40.7128, -74.0060)),
-                Some("US".to_string()),
-            )),
-            Some("operating_system".to_string()),
-            Some("node_type".to_string()),
-            Some("network_type".to_string()),
+        // Three intervals are not enough.
+        assert_eq!(data_state.block_interval_iqr(), None);
+
+        data_state.add_latest_block(create_test_block_detail(5, 66, 0, 0).await);
+        assert_eq!(
+            data_state.block_intervals(),
+            vec![1, 2, 3, 60]
+                .into_iter()
+                .map(Duration::from_secs)
+                .collect::<Vec<_>>()
         );
+        // The intervals are now 1, 2, 3, 4, and an outlier of 60 seconds, so
+        // the first and third quartiles are 2 and 4 seconds.
+        data_state.add_latest_block(create_test_block_detail(6, 70, 0, 0).await);
         assert_eq!(
-            node_identity_sender_1.send(node_identity_1.clone()).await,
-            Ok(())
+            data_state.block_interval_iqr(),
+            Some(Duration::from_secs(2))
         );
+    }
+
+    #[async_std::test]
+    async fn test_ewma_block_size() {
+        let mut data_state = DataState::default();
+        assert_eq!(data_state.ewma_block_size(0.5), None);
+
+        for (height, size) in [(1, 100), (2, 100), (3, 100), (4, 500)] {
+            data_state.add_latest_block(create_test_block_detail(height, 0, 0, size).await);
+        }
+
+        // The plain average is 200, but the most recent block is favored.
+        assert_eq!(data_state.ewma_block_size(0.5), Some(300.0));
+        assert_eq!(data_state.ewma_block_size(1.0), Some(500.0));
+
+        assert_eq!(data_state.ewma_block_size(0.0), None);
+        assert_eq!(data_state.ewma_block_size(1.5), None);
+        assert_eq!(data_state.ewma_block_size(f64::NAN), None);
+    }
 
+    #[async_std::test]
+    async fn test_tombstone_node() {
+        let (public_keys, stake_table) = create_test_stake_table(&[1, 1, 1]);
+        let mut data_state = DataState::new(Default::default(), Default::default(), stake_table);
+
+        data_state.add_latest_block(create_test_block_detail(1, 0, 0, 0).await);
+        data_state.add_latest_voters(create_test_voters(3, &[0, 2]));
+
+        assert!(data_state.tombstone_node(&public_keys[1]));
+        assert!(data_state.is_tombstoned(&public_keys[1]));
+        assert!(!data_state.tombstone_node(&BLSPubKey::generated_from_seed_indexed([0; 32], 3).0));
+
+        // The identity is kept in place, so the voters remain aligned.
+        assert_eq!(data_state.node_identity().count(), 3);
         assert_eq!(
-            node_identity_receiver_2.next().await,
-            Some(node_identity_1.clone())
+            data_state
+                .active_node_identity()
+                .map(|node_identity| *node_identity.public_key())
+                .collect::<Vec<_>>(),
+            vec![public_keys[0], public_keys[2]]
+        );
+        assert_eq!(
+            data_state.voting_pattern(&public_keys[0], 1),
+            Some(vec![true])
+        );
+        assert_eq!(
+            data_state.voting_pattern(&public_keys[1], 1),
+            Some(vec![false])
+        );
+        assert_eq!(
+            data_state.voting_pattern(&public_keys[2], 1),
+            Some(vec![true])
         );
 
-        {
-            let data_state = data_state.read().await;
-            // Latest blocks should now have a single entry
-            assert_eq!(data_state.node_identity().count(), 1);
-            assert_eq!(data_state.node_identity().next(), Some(&node_identity_1));
-        }
+        // The tombstoned node is not expected to vote.
+        assert_eq!(data_state.missing_voters(1), Some(vec![]));
+        assert_eq!(data_state.network_summary().known_node_count, 2);
 
-        // If we send a new node identity, it should result in a new node
-        // identity
+        // Announcing the node again revives it.
+        data_state.add_node_identity(NodeIdentity::from_public_key(public_keys[1]));
+        assert!(!data_state.is_tombstoned(&public_keys[1]));
+        assert_eq!(data_state.missing_voters(1), Some(vec![public_keys[1]]));
+    }
 
-        let public_key_2 = BLSPubKey::generated_from_seed_indexed([0; 32], 1).0;
-        let node_identity_2 = NodeIdentity::from_public_key(public_key_2);
+    #[async_std::test]
+    async fn test_blocks_with_unknown_proposer() {
+        let mut data_state = DataState::default();
+        let public_keys = (0..2)
+            .map(|index| BLSPubKey::generated_from_seed_indexed([0; 32], index).0)
+            .collect::<Vec<_>>();
+
+        // The first proposer is fully known, the second has a fee recipient
+        // mapping without a node identity, and the third is unregistered.
+        data_state.add_node_identity(NodeIdentity::from_public_key(public_keys[0]));
+        data_state.add_fee_recipient_identity(create_test_fee_account(1), public_keys[0]);
+        data_state.add_fee_recipient_identity(create_test_fee_account(2), public_keys[1]);
+
+        for height in 1..=3 {
+            data_state.add_latest_block(BlockDetail {
+                proposer_id: vec![create_test_fee_account(height)],
+                ..create_test_block_detail(height, 0, 0, 0).await
+            });
+        }
 
         assert_eq!(
-            node_identity_sender_1.send(node_identity_2.clone()).await,
-            Ok(())
+            data_state
+                .blocks_with_unknown_proposer()
+                .into_iter()
+                .map(|block| block.height)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
         );
+    }
+
+    #[async_std::test]
+    async fn test_blocks_proposed_by_now_known() {
+        let mut data_state = DataState::default();
+        let public_key = BLSPubKey::generated_from_seed_indexed([0; 32], 0).0;
+        data_state.add_fee_recipient_identity(create_test_fee_account(1), public_key);
 
+        for (height, proposer) in [(1, 1), (2, 2), (3, 1)] {
+            data_state.add_latest_block(BlockDetail {
+                proposer_id: vec![create_test_fee_account(proposer)],
+                ..create_test_block_detail(height, 0, 0, 0).await
+            });
+        }
+
+        // The proposer has not announced its identity yet.
+        assert!(data_state
+            .blocks_proposed_by_now_known(&public_key)
+            .is_empty());
+
+        // Once it has, the blocks that it proposed earlier are attributed to
+        // it.
+        data_state.add_node_identity(NodeIdentity::from_public_key(public_key));
         assert_eq!(
-            node_identity_receiver_2.next().await,
-            Some(node_identity_2.clone())
+            data_state
+                .blocks_proposed_by_now_known(&public_key)
+                .into_iter()
+                .map(|block| block.height)
+                .collect::<Vec<_>>(),
+            vec![1, 3]
         );
+    }
 
-        {
-            let data_state = data_state.read().await;
-            // Latest blocks should now have a single entry
-            assert_eq!(data_state.node_identity().count(), 2);
-            assert_eq!(data_state.node_identity().next(), Some(&node_identity_1));
-            assert_eq!(data_state.node_identity().last(), Some(&node_identity_2));
+    #[async_std::test]
+    async fn test_blocks_with_proposer_location() {
+        let mut data_state = DataState::default();
+        let public_keys = (0..2)
+            .map(|index| BLSPubKey::generated_from_seed_indexed([0; 32], index).0)
+            .collect::<Vec<_>>();
+        let location = LocationDetails::new(Some((40.7128, -74.0060)), Some("US".to_string()));
+
+        // The first proposer has a location, the second does not, and the
+        // third is unregistered.
+        let mut located = NodeIdentity::from_public_key(public_keys[0]);
+        located.location = Some(location.clone());
+        data_state.add_node_identity(located);
+        data_state.add_node_identity(NodeIdentity::from_public_key(public_keys[1]));
+        data_state.add_fee_recipient_identity(create_test_fee_account(1), public_keys[0]);
+        data_state.add_fee_recipient_identity(create_test_fee_account(2), public_keys[1]);
+
+        for height in 1..=3 {
+            data_state.add_latest_block(BlockDetail {
+                proposer_id: vec![create_test_fee_account(height)],
+                ..create_test_block_detail(height, 0, 0, 0).await
+            });
         }
 
-        if let Some(process_node_identity_task_handle) =
-            process_node_identity_task_handle.task_handle.take()
-        {
-            assert_eq!(process_node_identity_task_handle.cancel().await, None);
+        assert_eq!(
+            data_state
+                .blocks_with_proposer_location()
+                .into_iter()
+                .map(|(block, location)| (block.height, location.cloned()))
+                .collect::<Vec<_>>(),
+            vec![(1, Some(location)), (2, None), (3, None)]
+        );
+    }
+
+    #[async_std::test]
+    async fn test_block_rate_bounds() {
+        let mut data_state = DataState::default();
+        assert_eq!(data_state.block_rate_bounds(Duration::from_secs(60)), None);
+
+        // A burst of blocks every ten seconds, followed by a block a minute.
+        for (height, unix_timestamp) in [(1, 0), (2, 10), (3, 20), (4, 30), (5, 90), (6, 150)] {
+            data_state
+                .add_latest_block(create_test_block_detail(height, unix_timestamp, 0, 0).await);
         }
+
+        assert_eq!(
+            data_state.block_rate_bounds(Duration::from_secs(60)),
+            Some((1.0, 4.0))
+        );
+        assert_eq!(
+            data_state.block_rate_bounds(Duration::from_secs(120)),
+            Some((1.0, 2.5))
+        );
+        assert_eq!(data_state.block_rate_bounds(Duration::from_secs(200)), None);
+        assert_eq!(data_state.block_rate_bounds(Duration::ZERO), None);
+        assert_eq!(data_state.block_rate_bounds(Duration::MAX), None);
+        assert_eq!(
+            data_state.block_rate_bounds(Duration::from_secs(i64::MAX as u64)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_duplicate_location_nodes() {
+        let mut data_state: DataState = Default::default();
+        let public_keys = (0..4)
+            .map(|index| BLSPubKey::generated_from_seed_indexed([0; 32], index).0)
+            .collect::<Vec<_>>();
+        let location = LocationDetails::new(Some((40.7128, -74.0060)), Some("US".to_string()));
+
+        let create_node = |public_key: BLSPubKey, name: &str, location: Option<LocationDetails>| {
+            let mut node_identity = NodeIdentity::from_public_key(public_key);
+            node_identity.name = Some(name.to_string());
+            node_identity.location = location;
+            node_identity
+        };
+
+        data_state.add_node_identity(create_node(public_keys[0], "a", Some(location.clone())));
+        data_state.add_node_identity(create_node(public_keys[1], "b", Some(location.clone())));
+        data_state.add_node_identity(create_node(public_keys[2], "a", Some(location.clone())));
+        data_state.add_node_identity(create_node(public_keys[3], "a", None));
+
+        assert_eq!(
+            data_state.duplicate_location_nodes(),
+            vec![vec![public_keys[0], public_keys[2]]]
+        );
     }
 }
@@ -0,0 +1,34 @@
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+
+/// [NetworkSummary] represents a compact summary of the current health of
+/// the network, as derived from the information retained within the
+/// [DataState](super::DataState).
+///
+/// This is meant to be consumed by clients that would rather make a single
+/// request than assemble this information from several different snapshots.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkSummary {
+    /// latest_block_height is the height of the most recent block that has
+    /// been retained, if any.
+    pub latest_block_height: Option<u64>,
+
+    /// known_node_count is the number of nodes that we have identity
+    /// information for.
+    pub known_node_count: usize,
+
+    /// average_block_size is the average payload size of the retained blocks.
+    pub average_block_size: Option<f64>,
+
+    /// estimated_tps is the estimated number of transactions per second
+    /// observed across the retained blocks.
+    pub estimated_tps: Option<f64>,
+
+    /// distinct_voters is the number of nodes that have voted for at least
+    /// one of the retained blocks.
+    pub distinct_voters: usize,
+
+    /// total_stake is the total stake of the stake table snapshot that
+    /// voters are attributed against.
+    pub total_stake: U256,
+}
@@ -0,0 +1,26 @@
+use super::NodeIdentity;
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+
+/// [NodeProfile] combines everything that is known about a single node into
+/// one view, for the per-validator API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeProfile {
+    pub identity: NodeIdentity,
+
+    /// stake is the stake of the node within the stake table version that
+    /// is used for voter attribution, if the node is in the stake table.
+    pub stake: Option<U256>,
+
+    /// participation_rate is the fraction of the retained voters in which
+    /// the node voted, if any voters have been retained.
+    pub participation_rate: Option<f64>,
+
+    /// absence_streak is the number of the most recent retained voters in
+    /// which the node did not vote.
+    pub absence_streak: usize,
+
+    /// recent_voting_pattern is whether the node voted in each of the most
+    /// recent retained voters, from oldest to newest.
+    pub recent_voting_pattern: Vec<bool>,
+}
@@ -0,0 +1,75 @@
+use std::time::{Duration, Instant};
+
+/// DEFAULT_WARNING_INTERVAL is the default minimum amount of time between
+/// warnings that are emitted by a [RateLimitedWarning].
+pub const DEFAULT_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// [RateLimitedWarning] coalesces repeated occurrences of the same failure so
+/// that a persistent problem can be surfaced without flooding the logs.  At
+/// most one warning is permitted per interval, and each permitted warning
+/// carries the number of occurrences since the previous one.
+#[derive(Debug, Clone)]
+pub struct RateLimitedWarning {
+    interval: Duration,
+    last_warning: Option<Instant>,
+    occurrences: u64,
+}
+
+impl RateLimitedWarning {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_warning: None,
+            occurrences: 0,
+        }
+    }
+
+    /// [record] records a single occurrence at the given time.  If a warning
+    /// should be emitted, the number of occurrences since the last warning,
+    /// including this one, is returned.  Otherwise the occurrence is counted
+    /// towards the next warning, and [None] is returned.
+    pub fn record(&mut self, now: Instant) -> Option<u64> {
+        self.occurrences += 1;
+
+        if let Some(last_warning) = self.last_warning {
+            if now.saturating_duration_since(last_warning) < self.interval {
+                return None;
+            }
+        }
+
+        self.last_warning = Some(now);
+        Some(std::mem::take(&mut self.occurrences))
+    }
+}
+
+impl Default for RateLimitedWarning {
+    fn default() -> Self {
+        Self::new(DEFAULT_WARNING_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimitedWarning;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_rate_limited_warning_coalesces() {
+        let mut warning = RateLimitedWarning::new(Duration::from_secs(10));
+        let start = Instant::now();
+
+        // The first occurrence should always be reported.
+        assert_eq!(warning.record(start), Some(1));
+
+        // Occurrences within the interval should be suppressed.
+        for seconds in 1..10 {
+            assert_eq!(warning.record(start + Duration::from_secs(seconds)), None);
+        }
+
+        // Once the interval has elapsed, the suppressed occurrences should be
+        // reported together.
+        assert_eq!(warning.record(start + Duration::from_secs(10)), Some(10));
+        assert_eq!(warning.record(start + Duration::from_secs(11)), None);
+        assert_eq!(warning.record(start + Duration::from_secs(25)), Some(2));
+    }
+}
@@ -0,0 +1,108 @@
+use espresso_types::SeqTypes;
+use hotshot_query_service::explorer::BlockDetail;
+use time::OffsetDateTime;
+
+/// DEFAULT_SMOOTHING is the default weight that is given to each new sample
+/// by [RunningAggregates].
+pub const DEFAULT_SMOOTHING: f64 = 0.1;
+
+/// [RunningAggregates] maintains aggregate metrics for the processed blocks
+/// incrementally, without retaining the blocks themselves.
+///
+/// The aggregates are exponential moving averages, in which every new sample
+/// is given a weight of `smoothing`, and the previous average a weight of
+/// `1 - smoothing`.  The aggregates are independent of the retained buffers,
+/// so the eviction of blocks from those buffers has no effect on them, and
+/// the influence of older blocks decays over time instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunningAggregates {
+    smoothing: f64,
+    blocks: u64,
+    average_block_size: Option<f64>,
+    average_tps: Option<f64>,
+    average_participation: Option<f64>,
+    last_block_time: Option<OffsetDateTime>,
+}
+
+impl RunningAggregates {
+    /// [new] creates a new [RunningAggregates] with the given smoothing.
+    /// The smoothing is clamped to the range `(0, 1]`, where a smoothing of
+    /// one only considers the most recent sample.
+    pub fn new(smoothing: f64) -> Self {
+        let smoothing = if smoothing.is_finite() && smoothing > 0.0 {
+            smoothing.min(1.0)
+        } else {
+            DEFAULT_SMOOTHING
+        };
+
+        Self {
+            smoothing,
+            blocks: 0,
+            average_block_size: None,
+            average_tps: None,
+            average_participation: None,
+            last_block_time: None,
+        }
+    }
+
+    pub fn smoothing(&self) -> f64 {
+        self.smoothing
+    }
+
+    pub fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    pub fn average_block_size(&self) -> Option<f64> {
+        self.average_block_size
+    }
+
+    /// [average_tps] is the average of the transactions per second of each
+    /// block, measured from the time of the previous block.  Blocks that do
+    /// not advance the time are not sampled.
+    pub fn average_tps(&self) -> Option<f64> {
+        self.average_tps
+    }
+
+    /// [average_participation] is the average fraction of the stake that
+    /// participated in each block.
+    pub fn average_participation(&self) -> Option<f64> {
+        self.average_participation
+    }
+
+    /// [update] incorporates the given block, and the fraction of the stake
+    /// that participated in it, if known, into the aggregates.
+    pub fn update(&mut self, block: &BlockDetail<SeqTypes>, participation: Option<f64>) {
+        let smoothing = self.smoothing;
+        let apply = |average: &mut Option<f64>, sample: f64| {
+            *average = Some(match *average {
+                Some(average) => average + smoothing * (sample - average),
+                None => sample,
+            });
+        };
+
+        self.blocks += 1;
+        apply(&mut self.average_block_size, block.size as f64);
+
+        if let Some(last_block_time) = self.last_block_time {
+            let elapsed_seconds = (block.time.0 - last_block_time).as_seconds_f64();
+            if elapsed_seconds > 0.0 {
+                apply(
+                    &mut self.average_tps,
+                    block.num_transactions as f64 / elapsed_seconds,
+                );
+            }
+        }
+        self.last_block_time = Some(block.time.0);
+
+        if let Some(participation) = participation {
+            apply(&mut self.average_participation, participation);
+        }
+    }
+}
+
+impl Default for RunningAggregates {
+    fn default() -> Self {
+        Self::new(DEFAULT_SMOOTHING)
+    }
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// [Trend] represents the direction in which a metric is moving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trend {
+    Up,
+    Down,
+    Flat,
+}
@@ -0,0 +1,30 @@
+use bitvec::vec::BitVec;
+use espresso_types::SeqTypes;
+use hotshot_query_service::Leaf;
+
+/// [VoterAttributionSource] extracts the voters of a [Leaf], in the order of
+/// the stake table.  It allows the source of the attribution to be swapped
+/// for consensus configurations that expose the voters in a different
+/// certificate than the Quorum Certificate.
+pub trait VoterAttributionSource: Send + Sync {
+    /// [voters] returns whether each entry of the stake table voted for the
+    /// given [Leaf].
+    fn voters(&self, leaf: &Leaf<SeqTypes>) -> BitVec<u16>;
+}
+
+/// [QuorumCertificateVoters] is a [VoterAttributionSource] that attributes
+/// the voters from the signatures of the Quorum Certificate that justifies
+/// the [Leaf].  It is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QuorumCertificateVoters;
+
+impl VoterAttributionSource for QuorumCertificateVoters {
+    fn voters(&self, leaf: &Leaf<SeqTypes>) -> BitVec<u16> {
+        // We shouldn't ever have a BitVec that is empty, with the possible
+        // exception of the genesis block.
+        leaf.justify_qc()
+            .signatures
+            .as_ref()
+            .map_or(Default::default(), |sig| sig.1.clone())
+    }
+}
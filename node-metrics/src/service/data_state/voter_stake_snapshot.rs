@@ -0,0 +1,21 @@
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+
+/// [VoterStakeSnapshot] represents the stake weighted participation of the
+/// voters for a single block.
+///
+/// This is derived from the same computation that determines the voters
+/// [BitVec](bitvec::vec::BitVec) for the block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoterStakeSnapshot {
+    /// height is the height of the block that was voted on.
+    pub height: u64,
+
+    /// participating_stake is the total stake of the validators that
+    /// signed the quorum certificate for the block.
+    pub participating_stake: U256,
+
+    /// total_stake is the total stake of the stake table that the voters
+    /// were attributed against.
+    pub total_stake: U256,
+}